@@ -0,0 +1,143 @@
+#[cfg(feature = "bitboard")]
+use foxsee::{bitboard_perft, BitboardMoveGenerator, BitboardState};
+
+use foxsee::{decode_u32_mov, perft, MoveGenerator, State};
+
+use std::hint;
+use std::time::Instant;
+
+const BENCH_FEN_LIST: [&str; 4] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+];
+
+const NEW_ITER_COUNT: u32 = 10000;
+const MOV_GEN_ITER_COUNT: u32 = 200000;
+const PERFT_DEPTH: u8 = 5;
+
+fn main() {
+    let time_tracker = Instant::now();
+    for _ in 0..NEW_ITER_COUNT {
+        hint::black_box(MoveGenerator::new());
+    }
+
+    println!("MoveGenerator::new: {} ns", time_tracker.elapsed().as_nanos() / NEW_ITER_COUNT as u128);
+
+    let mov_generator = MoveGenerator::new();
+    let state_list: Vec<State> = BENCH_FEN_LIST.iter().map(|fen| State::new(fen)).collect();
+
+    let time_tracker = Instant::now();
+    let mut mov_count = 0;
+    for _ in 0..MOV_GEN_ITER_COUNT {
+        for state in state_list.iter() {
+            let (cap_list, non_cap_list) = mov_generator.gen_reg_mov_list(hint::black_box(state), false);
+            mov_count += cap_list.len() + non_cap_list.len();
+        }
+    }
+
+    let time_taken_millis = time_tracker.elapsed().as_millis().max(1);
+    println!("gen_reg_mov_list: {} moves in {} ms ({} moves/s)", mov_count, time_taken_millis, mov_count as u128 * 1000 / time_taken_millis);
+
+    let time_tracker = Instant::now();
+    let mut mov_count = 0;
+    for _ in 0..MOV_GEN_ITER_COUNT {
+        for state in state_list.iter() {
+            mov_count += mov_generator.gen_legal_mov_list(hint::black_box(state)).len();
+        }
+    }
+
+    let time_taken_millis = time_tracker.elapsed().as_millis().max(1);
+    println!("gen_legal_mov_list: {} moves in {} ms ({} moves/s)", mov_count, time_taken_millis, mov_count as u128 * 1000 / time_taken_millis);
+
+    let mut state_mov_list: Vec<(State, Vec<u32>)> = state_list.iter()
+        .map(|state| (state.clone(), mov_generator.gen_legal_mov_list(state).to_vec()))
+        .collect();
+
+    let time_tracker = Instant::now();
+    let mut mov_count = 0;
+    for _ in 0..MOV_GEN_ITER_COUNT {
+        for (state, mov_list) in state_mov_list.iter_mut() {
+            for mov in mov_list.iter() {
+                let (from, to, tp, promo) = decode_u32_mov(*mov);
+                let undo_info = state.do_mov(from, to, tp, promo);
+                hint::black_box(&*state);
+                state.undo_mov(from, to, tp, undo_info);
+            }
+
+            mov_count += mov_list.len();
+        }
+    }
+
+    let time_taken_millis = time_tracker.elapsed().as_millis().max(1);
+    println!("do_mov/undo_mov: {} moves in {} ms ({} moves/s)", mov_count, time_taken_millis, mov_count as u128 * 1000 / time_taken_millis);
+
+    for fen in BENCH_FEN_LIST.iter() {
+        let mut state = State::new(fen);
+
+        let time_tracker = Instant::now();
+        let node_count = perft(&mut state, &mov_generator, PERFT_DEPTH);
+        let time_taken_millis = time_tracker.elapsed().as_millis().max(1);
+
+        println!("perft {} {}: {} nodes in {} ms ({} nps)", PERFT_DEPTH, fen, node_count, time_taken_millis, node_count as u128 * 1000 / time_taken_millis);
+    }
+
+    #[cfg(feature = "bitboard")]
+    bench_bitboard(&state_list);
+}
+
+#[cfg(feature = "bitboard")]
+fn bench_bitboard(state_list: &[State]) {
+    let mov_generator = BitboardMoveGenerator::new();
+    let bb_state_list: Vec<BitboardState> = state_list.iter().map(BitboardState::from_state).collect();
+
+    let time_tracker = Instant::now();
+    let mut mov_count = 0;
+    for _ in 0..MOV_GEN_ITER_COUNT {
+        for bb_state in bb_state_list.iter() {
+            mov_count += mov_generator.gen_reg_mov_list(hint::black_box(bb_state)).len();
+        }
+    }
+
+    let time_taken_millis = time_tracker.elapsed().as_millis().max(1);
+    println!("bitboard gen_reg_mov_list: {} moves in {} ms ({} moves/s)", mov_count, time_taken_millis, mov_count as u128 * 1000 / time_taken_millis);
+
+    let time_tracker = Instant::now();
+    let mut mov_count = 0;
+    for _ in 0..MOV_GEN_ITER_COUNT {
+        for bb_state in bb_state_list.iter() {
+            mov_count += mov_generator.gen_legal_mov_list(hint::black_box(bb_state)).len();
+        }
+    }
+
+    let time_taken_millis = time_tracker.elapsed().as_millis().max(1);
+    println!("bitboard gen_legal_mov_list: {} moves in {} ms ({} moves/s)", mov_count, time_taken_millis, mov_count as u128 * 1000 / time_taken_millis);
+
+    let bb_state_mov_list: Vec<(BitboardState, Vec<u32>)> = bb_state_list.iter()
+        .map(|bb_state| (*bb_state, mov_generator.gen_legal_mov_list(bb_state)))
+        .collect();
+
+    let time_tracker = Instant::now();
+    let mut mov_count = 0;
+    for _ in 0..MOV_GEN_ITER_COUNT {
+        for (bb_state, mov_list) in bb_state_mov_list.iter() {
+            for mov in mov_list.iter() {
+                hint::black_box(hint::black_box(bb_state).do_mov(*mov));
+            }
+
+            mov_count += mov_list.len();
+        }
+    }
+
+    let time_taken_millis = time_tracker.elapsed().as_millis().max(1);
+    println!("bitboard do_mov: {} moves in {} ms ({} moves/s)", mov_count, time_taken_millis, mov_count as u128 * 1000 / time_taken_millis);
+
+    for (fen, bb_state) in BENCH_FEN_LIST.iter().zip(bb_state_list.iter()) {
+        let time_tracker = Instant::now();
+        let node_count = bitboard_perft(bb_state, &mov_generator, PERFT_DEPTH);
+        let time_taken_millis = time_tracker.elapsed().as_millis().max(1);
+
+        println!("bitboard perft {} {}: {} nodes in {} ms ({} nps)", PERFT_DEPTH, fen, node_count, time_taken_millis, node_count as u128 * 1000 / time_taken_millis);
+    }
+}
@@ -0,0 +1,681 @@
+use crate::{
+    def,
+    state::State,
+    util,
+    zob_keys,
+};
+
+const PIECE_TYPE_COUNT: usize = 12;
+const SQR_COUNT: usize = 64;
+const MAGIC_SEED: u64 = 0x_F0C5_EE00_B17B_0A2D;
+
+const RANK_2: u64 = 0xFF << 8;
+const RANK_7: u64 = 0xFF << 48;
+
+const N_STEPS: [(isize, isize); 8] = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+const K_STEPS: [(isize, isize); 8] = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+const WP_STEPS: [(isize, isize); 2] = [(1, -1), (1, 1)];
+const BP_STEPS: [(isize, isize); 2] = [(-1, -1), (-1, 1)];
+const B_DIRS: [(isize, isize); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+const R_DIRS: [(isize, isize); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+const PIECE_CODE_LIST: [u8; PIECE_TYPE_COUNT] = [def::WP, def::BP, def::WN, def::BN, def::WB, def::BB, def::WR, def::BR, def::WQ, def::BQ, def::WK, def::BK];
+
+#[inline]
+pub const fn map_index_to_sqr(index: usize) -> usize {
+    (index >> 4) * 8 + (index & 7)
+}
+
+#[inline]
+pub const fn map_sqr_to_index(sqr: usize) -> usize {
+    (sqr >> 3) * 16 + (sqr & 7)
+}
+
+#[inline]
+const fn map_player_to_bb_index(player: u8) -> usize {
+    (player & 1) as usize
+}
+
+#[inline]
+const fn get_piece_code(w_piece: u8, player: u8) -> u8 {
+    if player == def::PLAYER_W {
+        w_piece
+    } else {
+        w_piece - 1
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BitboardState {
+    pub piece_bbs: [u64; PIECE_TYPE_COUNT],
+    pub player_bbs: [u64; 2],
+    pub player: u8,
+    pub cas_rights: u8,
+    pub enp_square: usize,
+}
+
+impl BitboardState {
+    pub fn from_state(state: &State) -> BitboardState {
+        let mut bb_state = BitboardState {
+            piece_bbs: [0; PIECE_TYPE_COUNT],
+            player_bbs: [0; 2],
+            player: state.player,
+            cas_rights: state.cas_rights,
+            enp_square: state.enp_square,
+        };
+
+        for (index, piece) in state.squares.iter().enumerate() {
+            if def::is_index_valid(index) && *piece != 0 {
+                bb_state.toggle_piece(*piece, map_index_to_sqr(index));
+            }
+        }
+
+        bb_state
+    }
+
+    #[inline]
+    pub fn get_piece_bb(&self, piece: u8) -> u64 {
+        self.piece_bbs[zob_keys::map_piece_to_key_index(piece)]
+    }
+
+    #[inline]
+    pub fn get_occupied_bb(&self) -> u64 {
+        self.player_bbs[0] | self.player_bbs[1]
+    }
+
+    pub fn get_piece(&self, sqr: usize) -> u8 {
+        let sqr_bb = 1 << sqr;
+
+        let player_bb_index = if self.player_bbs[0] & sqr_bb != 0 {
+            0
+        } else if self.player_bbs[1] & sqr_bb != 0 {
+            1
+        } else {
+            return 0
+        };
+
+        for bb_index in (player_bb_index..PIECE_TYPE_COUNT).step_by(2) {
+            if self.piece_bbs[bb_index] & sqr_bb != 0 {
+                return PIECE_CODE_LIST[bb_index]
+            }
+        }
+
+        0
+    }
+
+    pub fn do_mov(&self, mov: u32) -> BitboardState {
+        let (from, to, tp, promo) = util::decode_u32_mov(mov);
+        let from_sqr = map_index_to_sqr(from);
+        let to_sqr = map_index_to_sqr(to);
+        let moving_piece = self.get_piece(from_sqr);
+
+        let mut next_state = *self;
+        next_state.enp_square = 0;
+
+        match tp {
+            def::MOV_ENP => {
+                let taken_index = if self.player == def::PLAYER_W {
+                    to - 16
+                } else {
+                    to + 16
+                };
+
+                next_state.toggle_piece(get_piece_code(def::WP, def::get_opposite_player(self.player)), map_index_to_sqr(taken_index));
+                next_state.toggle_piece(moving_piece, from_sqr);
+                next_state.toggle_piece(moving_piece, to_sqr);
+            },
+            def::MOV_CAS => {
+                let (r_from, r_to) = match to {
+                    def::CAS_SQUARE_WK => (def::CAS_SQUARE_WK + 1, def::CAS_SQUARE_WK - 1),
+                    def::CAS_SQUARE_WQ => (def::CAS_SQUARE_WQ - 2, def::CAS_SQUARE_WQ + 1),
+                    def::CAS_SQUARE_BK => (def::CAS_SQUARE_BK + 1, def::CAS_SQUARE_BK - 1),
+                    def::CAS_SQUARE_BQ => (def::CAS_SQUARE_BQ - 2, def::CAS_SQUARE_BQ + 1),
+                    _ => panic!("invalid castling square {}", to),
+                };
+
+                let r_piece = get_piece_code(def::WR, self.player);
+
+                next_state.toggle_piece(moving_piece, from_sqr);
+                next_state.toggle_piece(moving_piece, to_sqr);
+                next_state.toggle_piece(r_piece, map_index_to_sqr(r_from));
+                next_state.toggle_piece(r_piece, map_index_to_sqr(r_to));
+            },
+            _ => {
+                let taken_piece = self.get_piece(to_sqr);
+                if taken_piece != 0 {
+                    next_state.toggle_piece(taken_piece, to_sqr);
+                }
+
+                next_state.toggle_piece(moving_piece, from_sqr);
+
+                if tp == def::MOV_PROMO {
+                    next_state.toggle_piece(promo, to_sqr);
+                } else {
+                    next_state.toggle_piece(moving_piece, to_sqr);
+                }
+
+                if tp == def::MOV_CR_ENP {
+                    next_state.enp_square = (from + to) / 2;
+                }
+            },
+        }
+
+        next_state.cas_rights &= get_cas_rights_mask(from) & get_cas_rights_mask(to);
+        next_state.player = def::get_opposite_player(self.player);
+
+        next_state
+    }
+
+    #[inline]
+    fn toggle_piece(&mut self, piece: u8, sqr: usize) {
+        let sqr_bb = 1 << sqr;
+        self.piece_bbs[zob_keys::map_piece_to_key_index(piece)] ^= sqr_bb;
+        self.player_bbs[map_player_to_bb_index(piece)] ^= sqr_bb;
+    }
+}
+
+#[inline]
+fn get_cas_rights_mask(index: usize) -> u8 {
+    match index {
+        0 => 0b1011,
+        4 => 0b0011,
+        7 => 0b0111,
+        112 => 0b1110,
+        116 => 0b1100,
+        119 => 0b1101,
+        _ => 0b1111,
+    }
+}
+
+struct Magic {
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+pub struct BitboardMoveGenerator {
+    n_attack_table: [u64; SQR_COUNT],
+    k_attack_table: [u64; SQR_COUNT],
+    p_attack_table: [[u64; SQR_COUNT]; 2],
+
+    b_magic_table: Vec<Magic>,
+    r_magic_table: Vec<Magic>,
+    slide_attack_table: Vec<u64>,
+}
+
+impl Default for BitboardMoveGenerator {
+    fn default() -> Self {
+        BitboardMoveGenerator::new()
+    }
+}
+
+impl BitboardMoveGenerator {
+    pub fn new() -> BitboardMoveGenerator {
+        let mut seed = MAGIC_SEED;
+        let mut slide_attack_table = Vec::new();
+
+        let b_magic_table = (0..SQR_COUNT).map(|sqr| find_magic(sqr, &B_DIRS, &mut seed, &mut slide_attack_table)).collect();
+        let r_magic_table = (0..SQR_COUNT).map(|sqr| find_magic(sqr, &R_DIRS, &mut seed, &mut slide_attack_table)).collect();
+
+        BitboardMoveGenerator {
+            n_attack_table: gen_step_attack_table(&N_STEPS),
+            k_attack_table: gen_step_attack_table(&K_STEPS),
+            p_attack_table: [gen_step_attack_table(&WP_STEPS), gen_step_attack_table(&BP_STEPS)],
+
+            b_magic_table,
+            r_magic_table,
+            slide_attack_table,
+        }
+    }
+
+    #[inline]
+    pub fn get_b_attack_bb(&self, sqr: usize, occupied_bb: u64) -> u64 {
+        self.get_slide_attack_bb(&self.b_magic_table[sqr], occupied_bb)
+    }
+
+    #[inline]
+    pub fn get_r_attack_bb(&self, sqr: usize, occupied_bb: u64) -> u64 {
+        self.get_slide_attack_bb(&self.r_magic_table[sqr], occupied_bb)
+    }
+
+    #[inline]
+    fn get_slide_attack_bb(&self, magic: &Magic, occupied_bb: u64) -> u64 {
+        self.slide_attack_table[magic.offset + ((occupied_bb & magic.mask).wrapping_mul(magic.magic) >> magic.shift) as usize]
+    }
+
+    fn get_attack_bb(&self, piece: u8, sqr: usize, occupied_bb: u64) -> u64 {
+        if def::is_n(piece) {
+            self.n_attack_table[sqr]
+        } else if def::is_b(piece) {
+            self.get_b_attack_bb(sqr, occupied_bb)
+        } else if def::is_r(piece) {
+            self.get_r_attack_bb(sqr, occupied_bb)
+        } else if def::is_q(piece) {
+            self.get_b_attack_bb(sqr, occupied_bb) | self.get_r_attack_bb(sqr, occupied_bb)
+        } else {
+            self.k_attack_table[sqr]
+        }
+    }
+
+    pub fn is_sqr_under_attack(&self, state: &BitboardState, sqr: usize, attacker: u8) -> bool {
+        let occupied_bb = state.get_occupied_bb();
+        let q_bb = state.get_piece_bb(get_piece_code(def::WQ, attacker));
+
+        self.p_attack_table[map_player_to_bb_index(def::get_opposite_player(attacker))][sqr] & state.get_piece_bb(get_piece_code(def::WP, attacker)) != 0
+            || self.n_attack_table[sqr] & state.get_piece_bb(get_piece_code(def::WN, attacker)) != 0
+            || self.k_attack_table[sqr] & state.get_piece_bb(get_piece_code(def::WK, attacker)) != 0
+            || self.get_b_attack_bb(sqr, occupied_bb) & (state.get_piece_bb(get_piece_code(def::WB, attacker)) | q_bb) != 0
+            || self.get_r_attack_bb(sqr, occupied_bb) & (state.get_piece_bb(get_piece_code(def::WR, attacker)) | q_bb) != 0
+    }
+
+    pub fn is_in_check(&self, state: &BitboardState, player: u8) -> bool {
+        let k_bb = state.get_piece_bb(get_piece_code(def::WK, player));
+        k_bb != 0 && self.is_sqr_under_attack(state, k_bb.trailing_zeros() as usize, def::get_opposite_player(player))
+    }
+
+    pub fn gen_legal_mov_list(&self, state: &BitboardState) -> Vec<u32> {
+        let mut mov_list: Vec<u32> = self.gen_reg_mov_list(state).into_iter()
+            .filter(|mov| !self.is_in_check(&state.do_mov(*mov), state.player))
+            .collect();
+
+        mov_list.extend(self.gen_castle_mov_list(state));
+
+        mov_list
+    }
+
+    pub fn gen_reg_mov_list(&self, state: &BitboardState) -> Vec<u32> {
+        let player = state.player;
+        let player_bb_index = map_player_to_bb_index(player);
+        let own_bb = state.player_bbs[player_bb_index];
+        let opp_bb = state.player_bbs[player_bb_index ^ 1];
+        let occupied_bb = own_bb | opp_bb;
+
+        let mut mov_list = Vec::new();
+
+        let (forward, start_rank_bb) = if player == def::PLAYER_W {
+            (8, RANK_2)
+        } else {
+            (-8, RANK_7)
+        };
+
+        let mut p_bb = state.get_piece_bb(get_piece_code(def::WP, player));
+
+        while p_bb != 0 {
+            let from_sqr = p_bb.trailing_zeros() as usize;
+            p_bb &= p_bb - 1;
+
+            let to_sqr = (from_sqr as isize + forward) as usize;
+            if occupied_bb & (1 << to_sqr) == 0 {
+                add_p_mov(&mut mov_list, player, from_sqr, to_sqr);
+
+                let cr_enp_sqr = (to_sqr as isize + forward) as usize;
+                if start_rank_bb & (1 << from_sqr) != 0 && occupied_bb & (1 << cr_enp_sqr) == 0 {
+                    mov_list.push(util::encode_u32_mov(map_sqr_to_index(from_sqr), map_sqr_to_index(cr_enp_sqr), def::MOV_CR_ENP, 0));
+                }
+            }
+
+            let p_attack_bb = self.p_attack_table[player_bb_index][from_sqr];
+
+            let mut cap_bb = p_attack_bb & opp_bb;
+            while cap_bb != 0 {
+                add_p_mov(&mut mov_list, player, from_sqr, cap_bb.trailing_zeros() as usize);
+                cap_bb &= cap_bb - 1;
+            }
+
+            if state.enp_square != 0 && p_attack_bb & (1 << map_index_to_sqr(state.enp_square)) != 0 {
+                mov_list.push(util::encode_u32_mov(map_sqr_to_index(from_sqr), state.enp_square, def::MOV_ENP, 0));
+            }
+        }
+
+        for w_piece in [def::WN, def::WB, def::WR, def::WQ, def::WK].iter() {
+            let mut piece_bb = state.get_piece_bb(get_piece_code(*w_piece, player));
+
+            while piece_bb != 0 {
+                let from_sqr = piece_bb.trailing_zeros() as usize;
+                piece_bb &= piece_bb - 1;
+
+                let mut to_bb = self.get_attack_bb(*w_piece, from_sqr, occupied_bb) & !own_bb;
+                while to_bb != 0 {
+                    mov_list.push(util::encode_u32_mov(map_sqr_to_index(from_sqr), map_sqr_to_index(to_bb.trailing_zeros() as usize), def::MOV_REG, 0));
+                    to_bb &= to_bb - 1;
+                }
+            }
+        }
+
+        mov_list
+    }
+
+    pub fn gen_castle_mov_list(&self, state: &BitboardState) -> Vec<u32> {
+        let player = state.player;
+        let opp_player = def::get_opposite_player(player);
+        let occupied_bb = state.get_occupied_bb();
+        let k_bb = state.get_piece_bb(get_piece_code(def::WK, player));
+        let r_bb = state.get_piece_bb(get_piece_code(def::WR, player));
+
+        let (k_cas_right, q_cas_right, k_sqr) = if player == def::PLAYER_W {
+            (0b1000, 0b0100, 4)
+        } else {
+            (0b0010, 0b0001, 60)
+        };
+
+        let mut mov_list = Vec::new();
+
+        if k_bb & (1 << k_sqr) == 0 || self.is_sqr_under_attack(state, k_sqr, opp_player) {
+            return mov_list
+        }
+
+        if state.cas_rights & k_cas_right != 0
+            && r_bb & (1 << (k_sqr + 3)) != 0
+            && occupied_bb & (0b11 << (k_sqr + 1)) == 0
+            && !self.is_sqr_under_attack(state, k_sqr + 1, opp_player)
+            && !self.is_sqr_under_attack(state, k_sqr + 2, opp_player) {
+            mov_list.push(util::encode_u32_mov(map_sqr_to_index(k_sqr), map_sqr_to_index(k_sqr + 2), def::MOV_CAS, 0));
+        }
+
+        if state.cas_rights & q_cas_right != 0
+            && r_bb & (1 << (k_sqr - 4)) != 0
+            && occupied_bb & (0b111 << (k_sqr - 3)) == 0
+            && !self.is_sqr_under_attack(state, k_sqr - 1, opp_player)
+            && !self.is_sqr_under_attack(state, k_sqr - 2, opp_player) {
+            mov_list.push(util::encode_u32_mov(map_sqr_to_index(k_sqr), map_sqr_to_index(k_sqr - 2), def::MOV_CAS, 0));
+        }
+
+        mov_list
+    }
+}
+
+pub fn perft(state: &BitboardState, mov_generator: &BitboardMoveGenerator, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1
+    }
+
+    let mov_list = mov_generator.gen_legal_mov_list(state);
+
+    if depth == 1 {
+        return mov_list.len() as u64
+    }
+
+    mov_list.into_iter()
+        .map(|mov| perft(&state.do_mov(mov), mov_generator, depth - 1))
+        .sum()
+}
+
+pub fn divide(state: &BitboardState, mov_generator: &BitboardMoveGenerator, depth: u8) -> Vec<(u32, u64)> {
+    if depth == 0 {
+        return Vec::new()
+    }
+
+    mov_generator.gen_legal_mov_list(state).into_iter()
+        .map(|mov| (mov, perft(&state.do_mov(mov), mov_generator, depth - 1)))
+        .collect()
+}
+
+fn add_p_mov(mov_list: &mut Vec<u32>, player: u8, from_sqr: usize, to_sqr: usize) {
+    let from = map_sqr_to_index(from_sqr);
+    let to = map_sqr_to_index(to_sqr);
+
+    if !(8..56).contains(&to_sqr) {
+        for w_promo in [def::WQ, def::WR, def::WB, def::WN].iter() {
+            mov_list.push(util::encode_u32_mov(from, to, def::MOV_PROMO, get_piece_code(*w_promo, player)));
+        }
+    } else {
+        mov_list.push(util::encode_u32_mov(from, to, def::MOV_REG, 0));
+    }
+}
+
+fn is_on_board(rank: isize, file: isize) -> bool {
+    (0..8).contains(&rank) && (0..8).contains(&file)
+}
+
+fn gen_step_attack_table(step_list: &[(isize, isize)]) -> [u64; SQR_COUNT] {
+    let mut attack_table = [0; SQR_COUNT];
+
+    for (sqr, attack_bb) in attack_table.iter_mut().enumerate() {
+        let (rank, file) = ((sqr / 8) as isize, (sqr % 8) as isize);
+
+        for (rank_step, file_step) in step_list {
+            if is_on_board(rank + rank_step, file + file_step) {
+                *attack_bb |= 1 << ((rank + rank_step) * 8 + file + file_step);
+            }
+        }
+    }
+
+    attack_table
+}
+
+fn gen_slide_attack_bb(sqr: usize, occupied_bb: u64, dir_list: &[(isize, isize)]) -> u64 {
+    let mut attack_bb = 0;
+
+    for (rank_step, file_step) in dir_list {
+        let mut rank = (sqr / 8) as isize + rank_step;
+        let mut file = (sqr % 8) as isize + file_step;
+
+        while is_on_board(rank, file) {
+            let sqr_bb = 1 << (rank * 8 + file);
+            attack_bb |= sqr_bb;
+
+            if occupied_bb & sqr_bb != 0 {
+                break
+            }
+
+            rank += rank_step;
+            file += file_step;
+        }
+    }
+
+    attack_bb
+}
+
+fn gen_slide_mask(sqr: usize, dir_list: &[(isize, isize)]) -> u64 {
+    let mut mask = 0;
+
+    for (rank_step, file_step) in dir_list {
+        let mut rank = (sqr / 8) as isize + rank_step;
+        let mut file = (sqr % 8) as isize + file_step;
+
+        while is_on_board(rank + rank_step, file + file_step) {
+            mask |= 1 << (rank * 8 + file);
+            rank += rank_step;
+            file += file_step;
+        }
+    }
+
+    mask
+}
+
+fn find_magic(sqr: usize, dir_list: &[(isize, isize)], seed: &mut u64, slide_attack_table: &mut Vec<u64>) -> Magic {
+    let mask = gen_slide_mask(sqr, dir_list);
+    let shift = 64 - mask.count_ones();
+
+    let mut occupied_bb_list = Vec::new();
+    let mut attack_bb_list = Vec::new();
+    let mut occupied_bb: u64 = 0;
+
+    loop {
+        occupied_bb_list.push(occupied_bb);
+        attack_bb_list.push(gen_slide_attack_bb(sqr, occupied_bb, dir_list));
+
+        occupied_bb = occupied_bb.wrapping_sub(mask) & mask;
+        if occupied_bb == 0 {
+            break
+        }
+    }
+
+    let offset = slide_attack_table.len();
+    let table_size = occupied_bb_list.len();
+    slide_attack_table.resize(offset + table_size, 0);
+
+    let mut attempt_table = vec![0; table_size];
+    let mut attempt = 0;
+
+    loop {
+        let magic = zob_keys::next_key(seed) & zob_keys::next_key(seed) & zob_keys::next_key(seed);
+        if (mask.wrapping_mul(magic) >> 56).count_ones() < 6 {
+            continue
+        }
+
+        attempt += 1;
+        let mut is_magic_valid = true;
+
+        for (occupied_bb, attack_bb) in occupied_bb_list.iter().zip(attack_bb_list.iter()) {
+            let table_index = (occupied_bb.wrapping_mul(magic) >> shift) as usize;
+
+            if attempt_table[table_index] != attempt {
+                attempt_table[table_index] = attempt;
+                slide_attack_table[offset + table_index] = *attack_bb;
+            } else if slide_attack_table[offset + table_index] != *attack_bb {
+                is_magic_valid = false;
+                break
+            }
+        }
+
+        if is_magic_valid {
+            return Magic {
+                mask,
+                magic,
+                shift,
+                offset,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mov_gen::MoveGenerator;
+
+    fn perft_cmp_test_helper(state: &mut State, bb_state: &BitboardState, mov_generator: &MoveGenerator, bb_mov_generator: &BitboardMoveGenerator, depth: u8) -> u64 {
+        let mut mov_list = mov_generator.gen_legal_mov_list(state).to_vec();
+        let mut bb_mov_list = bb_mov_generator.gen_legal_mov_list(bb_state);
+        mov_list.sort();
+        bb_mov_list.sort();
+        assert_eq!(mov_list, bb_mov_list, "legal moves do not match for {}", state.to_fen());
+
+        if depth == 1 {
+            return mov_list.len() as u64
+        }
+
+        let mut node_count = 0;
+
+        for mov in mov_list {
+            let (from, to, tp, promo) = util::decode_u32_mov(mov);
+            let next_bb_state = bb_state.do_mov(mov);
+
+            let undo_info = state.do_mov(from, to, tp, promo);
+            let expected_bb_state = BitboardState::from_state(state);
+            assert_eq!(expected_bb_state.piece_bbs, next_bb_state.piece_bbs);
+            assert_eq!(expected_bb_state.enp_square, next_bb_state.enp_square);
+            assert_eq!(expected_bb_state.player, next_bb_state.player);
+
+            node_count += perft_cmp_test_helper(state, &next_bb_state, mov_generator, bb_mov_generator, depth - 1);
+            state.undo_mov(from, to, tp, undo_info);
+        }
+
+        node_count
+    }
+
+    #[test]
+    fn test_sqr_mapping() {
+        for index in 0..def::BOARD_SIZE {
+            if def::is_index_valid(index) {
+                assert_eq!(index, map_sqr_to_index(map_index_to_sqr(index)));
+            }
+        }
+
+        assert_eq!(0, map_index_to_sqr(util::map_sqr_notation_to_index("a1")));
+        assert_eq!(28, map_index_to_sqr(util::map_sqr_notation_to_index("e4")));
+        assert_eq!(63, map_index_to_sqr(util::map_sqr_notation_to_index("h8")));
+    }
+
+    #[test]
+    fn test_slide_attacks() {
+        let bb_mov_generator = BitboardMoveGenerator::new();
+        let mut seed = MAGIC_SEED;
+
+        for sqr in 0..SQR_COUNT {
+            for _ in 0..64 {
+                let occupied_bb = zob_keys::next_key(&mut seed) & zob_keys::next_key(&mut seed);
+                assert_eq!(gen_slide_attack_bb(sqr, occupied_bb, &B_DIRS), bb_mov_generator.get_b_attack_bb(sqr, occupied_bb));
+                assert_eq!(gen_slide_attack_bb(sqr, occupied_bb, &R_DIRS), bb_mov_generator.get_r_attack_bb(sqr, occupied_bb));
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_state() {
+        let state = State::new("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let bb_state = BitboardState::from_state(&state);
+
+        assert_eq!(def::WK, bb_state.get_piece(4));
+        assert_eq!(def::BQ, bb_state.get_piece(52));
+        assert_eq!(0, bb_state.get_piece(27));
+        assert_eq!(16, bb_state.player_bbs[0].count_ones());
+        assert_eq!(16, bb_state.player_bbs[1].count_ones());
+        assert_eq!(0b1111, bb_state.cas_rights);
+    }
+
+    #[test]
+    fn test_perft_cmp() {
+        let fen_list = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            "8/8/8/8/k2Pp2Q/8/8/3K4 b - d3 0 1",
+            "8/8/3p4/1Pp4r/1K5k/8/8/8 w - c6 0 1",
+            "4k3/8/8/8/8/5n2/8/R3K2R w KQ - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+            "8/P1k5/K7/8/8/8/8/8 w - - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            "2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1",
+            "3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1",
+        ];
+
+        let mov_generator = MoveGenerator::new();
+        let bb_mov_generator = BitboardMoveGenerator::new();
+
+        for fen in fen_list.iter() {
+            let mut state = State::new(fen);
+            let bb_state = BitboardState::from_state(&state);
+
+            let node_count = perft_cmp_test_helper(&mut state, &bb_state, &mov_generator, &bb_mov_generator, 3);
+            assert_eq!(crate::perft::perft(&mut state, &mov_generator, 3), node_count);
+            assert_eq!(node_count, perft(&bb_state, &bb_mov_generator, 3));
+        }
+    }
+
+    #[test]
+    fn test_perft() {
+        let bb_mov_generator = BitboardMoveGenerator::new();
+
+        let perft_test_list: [(&str, &[u64]); 4] = [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &[20, 400, 8902, 197281, 4865609]),
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[48, 2039, 97862, 4085603]),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2812, 43238, 674624]),
+            ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", &[6, 264, 9467, 422333]),
+        ];
+
+        for (fen, expected_node_count_list) in perft_test_list.iter() {
+            let bb_state = BitboardState::from_state(&State::new(fen));
+
+            for (depth, expected_node_count) in expected_node_count_list.iter().enumerate() {
+                assert_eq!(*expected_node_count, perft(&bb_state, &bb_mov_generator, depth as u8 + 1), "perft {} failed for {}", depth + 1, fen);
+            }
+        }
+    }
+
+    #[test]
+    fn test_divide() {
+        let bb_state = BitboardState::from_state(&State::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+        let bb_mov_generator = BitboardMoveGenerator::new();
+
+        let divide_list = divide(&bb_state, &bb_mov_generator, 3);
+        assert_eq!(20, divide_list.len());
+        assert_eq!(8902, divide_list.iter().map(|(_mov, node_count)| node_count).sum::<u64>());
+    }
+}
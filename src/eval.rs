@@ -19,6 +19,18 @@ static ENDGAME_PAWN_EXTRA_VAL: i32 = 50;
 static DUP_PAWN_PEN: i32 = 30;
 static KING_SAFETY: i32 = 50;
 static DRAW_PEN: i32 = 200;
+static DRAWISH_SCALE_DIVISOR: i32 = 8;
+
+// pawnless (stronger side, weaker side) piece counts as [queens, rooks, bishops, knights]
+static DRAWISH_MATERIAL_LIST: [([i32; 4], [i32; 4]); 7] = [
+    ([0, 1, 0, 0], [0, 0, 1, 0]),
+    ([0, 1, 0, 0], [0, 0, 0, 1]),
+    ([0, 0, 0, 2], [0, 0, 0, 0]),
+    ([0, 0, 1, 0], [0, 0, 1, 0]),
+    ([0, 0, 1, 0], [0, 0, 0, 1]),
+    ([0, 0, 0, 1], [0, 0, 1, 0]),
+    ([0, 0, 0, 1], [0, 0, 0, 1]),
+];
 
 static WK_SQR_VAL: [i32; def::BOARD_SIZE] = [
      20, 30, 10,  0,  0, 10, 30, 20, 0,  0,  0,  0,  0,  0,  0,  0,
@@ -216,135 +228,149 @@ pub fn val_of(piece: u8) -> i32 {
     }
 }
 
-pub fn eval_state(state: &State) -> i32 {
-    let squares = state.squares;
-    let mut index = 0;
-    let mut base_score = 0;
-    let mut midgame_score = 0;
-    let mut endgame_score = 0;
-
-    let mut wp_count = 0;
-    let mut bp_count = 0;
-    let mut wq_count = 0;
-    let mut bq_count = 0;
-    let mut wb_count = 0;
-    let mut bb_count = 0;
-    let mut w_piece_count = 0;
-    let mut b_piece_count = 0;
-    let mut wk_safe = true;
-    let mut bk_safe = true;
-
-    while index < def::BOARD_SIZE {
-        if !def::is_index_valid(index) {
-            index += 8;
-        }
+pub const PIECE_COUNT_LIST_SIZE: usize = def::WK as usize + 1;
 
-        let moving_piece = squares[index];
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EvalSum {
+    pub material_score: i32,
+    pub midgame_score: i32,
+    pub endgame_score: i32,
+    pub piece_count_list: [i32; PIECE_COUNT_LIST_SIZE],
+    pub b_sqr_color_count_list: [i32; 2],
+}
 
-        if moving_piece == 0 {
-            index += 1;
-            continue
+impl EvalSum {
+    pub fn new(squares: &[u8; def::BOARD_SIZE]) -> EvalSum {
+        let mut eval_sum = EvalSum {
+            material_score: 0,
+            midgame_score: 0,
+            endgame_score: 0,
+            piece_count_list: [0; PIECE_COUNT_LIST_SIZE],
+            b_sqr_color_count_list: [0; 2],
+        };
+
+        for index in 0..def::BOARD_SIZE {
+            let piece = squares[index];
+
+            if piece == 0 || !def::is_index_valid(index) {
+                continue
+            }
+
+            eval_sum.add_piece(piece, index, 1);
+            eval_sum.add_pawn_structure(squares, index, 1);
         }
 
-        match moving_piece {
-            def::WP => {
-                base_score += P_VAL;
-                midgame_score += WP_SQR_VAL[index];
+        eval_sum
+    }
 
-                if squares[index + 16] == 0 {
-                    endgame_score += END_WP_SQR_VAL[index];
-                } else if squares[index + 16] == def::WP {
-                    midgame_score -= DUP_PAWN_PEN;
-                    endgame_score -= DUP_PAWN_PEN;
-                }
+    pub fn set_sqr(&mut self, squares: &mut [u8; def::BOARD_SIZE], index: usize, piece: u8) {
+        let old_piece = squares[index];
 
-                wp_count += 1;
-            },
-            def::BP => {
-                base_score -= P_VAL;
-                midgame_score -= BP_SQR_VAL[index];
+        self.update_pawn_structure(squares, index, -1);
+        if old_piece != 0 {
+            self.add_piece(old_piece, index, -1);
+        }
 
-                if squares[index - 16] == 0 {
-                    endgame_score -= END_BP_SQR_VAL[index];
-                }else if squares[index - 16] == def::BP {
-                    midgame_score += DUP_PAWN_PEN;
-                    endgame_score += DUP_PAWN_PEN;
-                }
+        squares[index] = piece;
 
-                bp_count += 1;
-            },
+        if piece != 0 {
+            self.add_piece(piece, index, 1);
+        }
+        self.update_pawn_structure(squares, index, 1);
+    }
 
-            def::WN => {
-                base_score += N_VAL;
-                midgame_score += WN_SQR_VAL[index];
-                w_piece_count += 1;
-            },
-            def::BN => {
-                base_score -= N_VAL;
-                midgame_score -= BN_SQR_VAL[index];
-                b_piece_count += 1;
-            },
+    fn update_pawn_structure(&mut self, squares: &[u8; def::BOARD_SIZE], index: usize, sign: i32) {
+        self.add_pawn_structure(squares, index, sign);
 
-            def::WB => {
-                base_score += B_VAL;
-                midgame_score += WB_SQR_VAL[index];
-                wb_count += 1;
-                w_piece_count += 1;
-            },
-            def::BB => {
-                base_score -= B_VAL;
-                midgame_score -= BB_SQR_VAL[index];
-                bb_count += 1;
-                b_piece_count += 1;
-            },
+        if index >= 16 && squares[index - 16] == def::WP {
+            self.add_pawn_structure(squares, index - 16, sign);
+        }
 
-            def::WR => {
-                base_score += R_VAL;
-                midgame_score += WR_SQR_VAL[index];
-                w_piece_count += 1;
-            },
-            def::BR => {
-                base_score -= R_VAL;
-                midgame_score -= BR_SQR_VAL[index];
-                b_piece_count += 1;
-            },
+        if index + 16 < def::BOARD_SIZE && squares[index + 16] == def::BP {
+            self.add_pawn_structure(squares, index + 16, sign);
+        }
+    }
 
-            def::WQ => {
-                base_score += Q_VAL;
-                midgame_score += WQ_SQR_VAL[index];
-                wq_count += 1;
-                w_piece_count += 1;
-            },
-            def::BQ => {
-                base_score -= Q_VAL;
-                midgame_score -= BQ_SQR_VAL[index];
-                bq_count += 1;
-                b_piece_count += 1;
-            },
+    fn add_piece(&mut self, piece: u8, index: usize, sign: i32) {
+        self.piece_count_list[piece as usize] += sign;
+
+        if def::is_b(piece) {
+            self.b_sqr_color_count_list[get_sqr_color(index)] += sign;
+        }
 
-            def::WK => {
-                base_score += K_VAL;
-                midgame_score += WK_SQR_VAL[index];
-                endgame_score += END_WK_SQR_VAL[index];
+        let (material_score, midgame_score, endgame_score) = match piece {
+            def::WP => (P_VAL, WP_SQR_VAL[index], 0),
+            def::WN => (N_VAL, WN_SQR_VAL[index], 0),
+            def::WB => (B_VAL, WB_SQR_VAL[index], 0),
+            def::WR => (R_VAL, WR_SQR_VAL[index], 0),
+            def::WQ => (Q_VAL, WQ_SQR_VAL[index], 0),
+            def::WK => (K_VAL, WK_SQR_VAL[index], END_WK_SQR_VAL[index]),
+
+            def::BP => (-P_VAL, -BP_SQR_VAL[index], 0),
+            def::BN => (-N_VAL, -BN_SQR_VAL[index], 0),
+            def::BB => (-B_VAL, -BB_SQR_VAL[index], 0),
+            def::BR => (-R_VAL, -BR_SQR_VAL[index], 0),
+            def::BQ => (-Q_VAL, -BQ_SQR_VAL[index], 0),
+            def::BK => (-K_VAL, -BK_SQR_VAL[index], -END_BK_SQR_VAL[index]),
+
+            _ => (0, 0, 0),
+        };
+
+        self.material_score += material_score * sign;
+        self.midgame_score += midgame_score * sign;
+        self.endgame_score += endgame_score * sign;
+    }
 
-                if index < 112 && !def::on_same_side(def::WK, squares[index + 16]) {
-                    wk_safe = false;
+    fn add_pawn_structure(&mut self, squares: &[u8; def::BOARD_SIZE], index: usize, sign: i32) {
+        match squares[index] {
+            def::WP => {
+                if squares[index + 16] == 0 {
+                    self.endgame_score += END_WP_SQR_VAL[index] * sign;
+                } else if squares[index + 16] == def::WP {
+                    self.midgame_score -= DUP_PAWN_PEN * sign;
+                    self.endgame_score -= DUP_PAWN_PEN * sign;
                 }
             },
-            def::BK => {
-                base_score -= K_VAL;
-                midgame_score -= BK_SQR_VAL[index];
-                endgame_score -= END_BK_SQR_VAL[index];
-
-                if index > 16 && !def::on_same_side(def::BK, squares[index - 16]) {
-                    bk_safe = false;
+            def::BP => {
+                if squares[index - 16] == 0 {
+                    self.endgame_score -= END_BP_SQR_VAL[index] * sign;
+                } else if squares[index - 16] == def::BP {
+                    self.midgame_score += DUP_PAWN_PEN * sign;
+                    self.endgame_score += DUP_PAWN_PEN * sign;
                 }
             },
             _ => {},
         }
-
-        index += 1;
     }
+}
+
+pub fn eval_state(state: &State) -> i32 {
+    let eval_sum = &state.eval_sum;
+    debug_assert_eq!(EvalSum::new(&state.squares), *eval_sum, "incremental eval does not match for {}", state.to_fen());
+
+    let squares = state.squares;
+    let mut base_score = eval_sum.material_score;
+    let mut midgame_score = eval_sum.midgame_score;
+    let mut endgame_score = eval_sum.endgame_score;
+
+    let piece_count_list = &eval_sum.piece_count_list;
+    let wp_count = piece_count_list[def::WP as usize];
+    let bp_count = piece_count_list[def::BP as usize];
+    let wq_count = piece_count_list[def::WQ as usize];
+    let bq_count = piece_count_list[def::BQ as usize];
+    let wr_count = piece_count_list[def::WR as usize];
+    let br_count = piece_count_list[def::BR as usize];
+    let wb_count = piece_count_list[def::WB as usize];
+    let bb_count = piece_count_list[def::BB as usize];
+    let wn_count = piece_count_list[def::WN as usize];
+    let bn_count = piece_count_list[def::BN as usize];
+    let w_piece_count = wq_count + wr_count + wb_count + wn_count;
+    let b_piece_count = bq_count + br_count + bb_count + bn_count;
+
+    let wk_index = state.wk_index;
+    let bk_index = state.bk_index;
+    let wk_safe = squares[wk_index] != def::WK || wk_index >= 112 || def::on_same_side(def::WK, squares[wk_index + 16]);
+    let bk_safe = squares[bk_index] != def::BK || bk_index <= 16 || def::on_same_side(def::BK, squares[bk_index - 16]);
 
     if (wq_count == 0 || bq_count == 0 || w_piece_count == 1 || b_piece_count == 1) && (w_piece_count < 4 || b_piece_count < 4) {
         if wp_count < 5 || bp_count < 5 {
@@ -367,6 +393,14 @@ pub fn eval_state(state: &State) -> i32 {
             base_score += DRAW_PEN;
         }
 
+        if is_material_draw(state) {
+            return 0
+        }
+
+        if is_drawish(piece_count_list) {
+            return (base_score + endgame_score) / DRAWISH_SCALE_DIVISOR
+        }
+
         return base_score + endgame_score
     }
 
@@ -381,11 +415,48 @@ pub fn eval_state(state: &State) -> i32 {
     base_score + midgame_score
 }
 
+pub fn is_material_draw(state: &State) -> bool {
+    let eval_sum = &state.eval_sum;
+    let piece_count_list = &eval_sum.piece_count_list;
+    if [def::WP, def::BP, def::WR, def::BR, def::WQ, def::BQ].iter().any(|piece| piece_count_list[*piece as usize] > 0) {
+        return false
+    }
+
+    let n_count = piece_count_list[def::WN as usize] + piece_count_list[def::BN as usize];
+    let [dark_b_count, light_b_count] = eval_sum.b_sqr_color_count_list;
+
+    match n_count {
+        0 => dark_b_count == 0 || light_b_count == 0,
+        1 => dark_b_count == 0 && light_b_count == 0,
+        _ => false,
+    }
+}
+
+#[inline]
+fn get_sqr_color(index: usize) -> usize {
+    ((index >> 4) + (index & 7)) & 1
+}
+
+fn is_drawish(piece_count_list: &[i32; PIECE_COUNT_LIST_SIZE]) -> bool {
+    if piece_count_list[def::WP as usize] > 0 || piece_count_list[def::BP as usize] > 0 {
+        return false
+    }
+
+    let w_material = [def::WQ, def::WR, def::WB, def::WN].map(|piece| piece_count_list[piece as usize]);
+    let b_material = [def::BQ, def::BR, def::BB, def::BN].map(|piece| piece_count_list[piece as usize]);
+
+    DRAWISH_MATERIAL_LIST.iter().any(|(material, opponent_material)| {
+        (*material == w_material && *opponent_material == b_material) || (*material == b_material && *opponent_material == w_material)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
+        mov_gen::MoveGenerator,
         state::State,
+        util,
     };
 
     #[test]
@@ -396,4 +467,78 @@ mod tests {
         let state = State::new("4k2r/pbppnppp/1bn5/4p3/2B5/2N1P3/PPPP1PPP/R1BQK2R b KQk - 0 1");
         assert_eq!(1245, eval_state(&state));
     }
+
+    #[test]
+    fn test_eval_sum_incremental() {
+        let mut state = State::new("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1");
+        assert_eq!(2, state.eval_sum.piece_count_list[def::WN as usize]);
+        assert_eq!(7, state.eval_sum.piece_count_list[def::BP as usize]);
+
+        let mov_generator = MoveGenerator::new();
+
+        for mov_str in ["c4c5", "b2a1q", "d1a1", "e8c8", "a7a8n", "g6e4"].iter() {
+            let mov = *mov_generator.gen_legal_mov_list(&state).iter().find(|mov| util::format_mov(**mov) == *mov_str).unwrap();
+            let (from, to, tp, promo) = util::decode_u32_mov(mov);
+            state.do_mov(from, to, tp, promo);
+
+            let fen_state = State::new(&state.to_fen());
+            assert_eq!(fen_state.eval_sum, state.eval_sum, "eval sum does not match after {}", mov_str);
+            assert_eq!(eval_state(&fen_state), eval_state(&state));
+        }
+
+        assert_eq!(1, state.eval_sum.piece_count_list[def::WQ as usize]);
+        assert_eq!(3, state.eval_sum.piece_count_list[def::WN as usize]);
+        assert_eq!(6, state.eval_sum.piece_count_list[def::BP as usize]);
+    }
+
+    #[test]
+    fn test_material_draw() {
+        assert!(is_material_draw(&State::new("8/8/4k3/8/8/3K4/8/8 w - - 0 1")));
+        assert!(is_material_draw(&State::new("8/8/4k3/8/8/3K4/8/5B2 w - - 0 1")));
+        assert!(is_material_draw(&State::new("8/8/4k3/8/8/3K4/8/5n2 b - - 0 1")));
+        assert!(is_material_draw(&State::new("8/8/4k3/3b4/8/3K4/8/5B2 w - - 0 1")));
+        assert!(is_material_draw(&State::new("8/8/4k1b1/8/8/3K4/8/3B1B2 w - - 0 1")));
+
+        assert!(!is_material_draw(&State::new("8/8/4k3/2b5/8/3K4/8/5B2 w - - 0 1")));
+        assert!(!is_material_draw(&State::new("8/8/4kb2/8/8/3K4/8/3B1B2 w - - 0 1")));
+        assert!(!is_material_draw(&State::new("8/8/4k3/8/8/3K4/8/4NB2 w - - 0 1")));
+        assert!(!is_material_draw(&State::new("8/8/4k3/8/8/3K4/8/4NN2 w - - 0 1")));
+        assert!(!is_material_draw(&State::new("8/8/4k3/8/8/3K4/8/5n1N w - - 0 1")));
+        assert!(!is_material_draw(&State::new("8/8/4k3/8/8/3K4/4P3/8 w - - 0 1")));
+        assert!(!is_material_draw(&State::new("8/8/4k3/8/8/3K4/8/7R w - - 0 1")));
+
+        assert_eq!(0, eval_state(&State::new("8/8/4k3/8/8/3K4/8/5B2 w - - 0 1")));
+        assert_eq!(0, eval_state(&State::new("8/8/4k3/3b4/8/3K4/8/5B2 b - - 0 1")));
+    }
+
+    #[test]
+    fn test_eval_drawish() {
+        let knn_score = eval_state(&State::new("8/8/4k3/8/8/3K4/8/4NN2 w - - 0 1"));
+        assert!(knn_score > 0 && knn_score < ADVANCE_VAL);
+
+        let krkb_score = eval_state(&State::new("8/8/4k3/2b5/8/3K4/8/7R w - - 0 1"));
+        assert!(krkb_score > 0 && krkb_score < ADVANCE_VAL);
+
+        let kbkr_score = eval_state(&State::new("8/8/4k3/2r5/8/3K4/8/7B w - - 0 1"));
+        assert!(kbkr_score < 0 && kbkr_score > -ADVANCE_VAL);
+
+        assert!(eval_state(&State::new("8/8/4k3/8/8/3K4/8/7R w - - 0 1")) > ADVANCE_VAL);
+        assert!(eval_state(&State::new("8/8/4k3/8/8/3K4/8/4BN2 w - - 0 1")) > ADVANCE_VAL);
+        assert!(eval_state(&State::new("8/8/4k3/2b5/8/3K4/4P3/7R w - - 0 1")) > ADVANCE_VAL);
+        assert!(eval_state(&State::new("8/8/4k3/2r5/8/3K4/8/7Q w - - 0 1")) > ADVANCE_VAL);
+    }
+
+    #[test]
+    fn test_is_drawish() {
+        assert!(is_drawish(&State::new("8/8/4k3/2b5/8/3K4/8/7R w - - 0 1").eval_sum.piece_count_list));
+        assert!(is_drawish(&State::new("8/8/4k3/2R5/8/3K4/8/7n w - - 0 1").eval_sum.piece_count_list));
+        assert!(is_drawish(&State::new("8/8/4k3/8/8/3K4/8/4nn2 w - - 0 1").eval_sum.piece_count_list));
+        assert!(is_drawish(&State::new("8/8/4k3/2b5/8/3K4/8/7N w - - 0 1").eval_sum.piece_count_list));
+
+        assert!(!is_drawish(&State::new("8/8/4k3/2r5/8/3K4/8/7Q w - - 0 1").eval_sum.piece_count_list));
+        assert!(!is_drawish(&State::new("8/8/4k3/2b5/4p3/3K4/8/7R w - - 0 1").eval_sum.piece_count_list));
+        assert!(!is_drawish(&State::new("8/8/4k3/2b5/8/3K4/4P3/7R w - - 0 1").eval_sum.piece_count_list));
+        assert!(!is_drawish(&State::new("8/8/4k3/2b5/8/3K4/8/6BR w - - 0 1").eval_sum.piece_count_list));
+        assert!(!is_drawish(&State::new("8/8/4k3/8/8/3K4/8/4BN2 w - - 0 1").eval_sum.piece_count_list));
+    }
 }
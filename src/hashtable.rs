@@ -0,0 +1,117 @@
+use crate::eval;
+
+use std::mem;
+
+pub const HASH_TYPE_EXACT: u8 = 1;
+pub const HASH_TYPE_LOWER: u8 = 2;
+pub const HASH_TYPE_UPPER: u8 = 3;
+
+const MB_SIZE: usize = 1024 * 1024;
+
+#[derive(Clone, Copy, Default)]
+struct HashEntry {
+    key: u64,
+    mov: u32,
+    score: i32,
+    depth: u8,
+    flag: u8,
+}
+
+pub struct HashTable {
+    table: Vec<HashEntry>,
+}
+
+impl HashTable {
+    pub fn new(size_mb: usize) -> HashTable {
+        let entry_count = (size_mb * MB_SIZE / mem::size_of::<HashEntry>()).max(1);
+
+        HashTable {
+            table: vec![HashEntry::default(); entry_count],
+        }
+    }
+
+    pub fn get(&self, key: u64, ply: u8) -> Option<(u8, u8, i32, u32)> {
+        let entry = &self.table[(key % self.table.len() as u64) as usize];
+
+        if entry.flag == 0 || entry.key != key {
+            return None
+        }
+
+        let mut score = entry.score;
+        if score > eval::TERM_VAL {
+            score -= ply as i32;
+        } else if score < -eval::TERM_VAL {
+            score += ply as i32;
+        }
+
+        Some((entry.depth, entry.flag, score, entry.mov))
+    }
+
+    pub fn set(&mut self, key: u64, depth: u8, ply: u8, flag: u8, mut score: i32, mov: u32) {
+        let entry_index = (key % self.table.len() as u64) as usize;
+        let entry = &mut self.table[entry_index];
+
+        if entry.key == key && entry.depth > depth {
+            return
+        }
+
+        if score > eval::TERM_VAL {
+            score += ply as i32;
+        } else if score < -eval::TERM_VAL {
+            score -= ply as i32;
+        }
+
+        *entry = HashEntry {
+            key,
+            mov,
+            score,
+            depth,
+            flag,
+        };
+    }
+
+    pub fn clear(&mut self) {
+        for entry in self.table.iter_mut() {
+            *entry = HashEntry::default();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_get() {
+        let mut hash_table = HashTable::new(1);
+
+        assert_eq!(None, hash_table.get(12345, 0));
+
+        hash_table.set(12345, 5, 0, HASH_TYPE_LOWER, 120, 99);
+        assert_eq!(Some((5, HASH_TYPE_LOWER, 120, 99)), hash_table.get(12345, 0));
+        assert_eq!(None, hash_table.get(12345 + hash_table.table.len() as u64, 0));
+
+        hash_table.set(12345, 3, 0, HASH_TYPE_EXACT, 50, 98);
+        assert_eq!(Some((5, HASH_TYPE_LOWER, 120, 99)), hash_table.get(12345, 0));
+
+        hash_table.set(12345 + hash_table.table.len() as u64, 1, 0, HASH_TYPE_UPPER, -30, 0);
+        assert_eq!(None, hash_table.get(12345, 0));
+
+        hash_table.clear();
+        assert_eq!(None, hash_table.get(12345 + hash_table.table.len() as u64, 0));
+    }
+
+    #[test]
+    fn test_mate_score_adjustment() {
+        let mut hash_table = HashTable::new(1);
+
+        hash_table.set(1, 4, 3, HASH_TYPE_EXACT, eval::K_VAL - 7, 0);
+        assert_eq!(Some((4, HASH_TYPE_EXACT, eval::K_VAL - 5, 0)), hash_table.get(1, 1));
+
+        hash_table.set(2, 4, 3, HASH_TYPE_EXACT, -eval::K_VAL + 7, 0);
+        assert_eq!(Some((4, HASH_TYPE_EXACT, -eval::K_VAL + 5, 0)), hash_table.get(2, 1));
+
+        hash_table.set(3, 4, 3, HASH_TYPE_EXACT, 250, 0);
+        assert_eq!(Some((4, HASH_TYPE_EXACT, 250, 0)), hash_table.get(3, 1));
+    }
+}
@@ -0,0 +1,42 @@
+//! FoxSEE as a library: positions, move generation and search.
+//!
+//! ```
+//! use foxsee::{decode_u32_mov, MoveGenerator, SearchEngine, SearchLimits, State};
+//!
+//! let mut state = State::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+//! let mov_generator = MoveGenerator::new();
+//! assert_eq!(20, mov_generator.gen_legal_mov_list(&state).len());
+//!
+//! let (from, to, tp, promo) = decode_u32_mov(mov_generator.gen_legal_mov_list(&state)[0]);
+//! let undo_info = state.do_mov(from, to, tp, promo);
+//! state.undo_mov(from, to, tp, undo_info);
+//!
+//! let mut search_engine = SearchEngine::new(16);
+//! let search_result = search_engine.search(&mut state, &SearchLimits { depth: Some(3), ..Default::default() });
+//! assert_eq!(search_result.best_mov, search_result.pv[0]);
+//! ```
+
+#[cfg(feature = "bitboard")]
+mod bitboard;
+mod def;
+mod eval;
+mod hashtable;
+mod mov_gen;
+mod mov_list;
+mod mov_tbl;
+mod options;
+mod perft;
+mod search;
+mod state;
+mod uci;
+mod util;
+mod zob_keys;
+
+#[cfg(feature = "bitboard")]
+pub use bitboard::{divide as bitboard_divide, perft as bitboard_perft, BitboardMoveGenerator, BitboardState};
+pub use mov_gen::MoveGenerator;
+pub use perft::{divide, perft};
+pub use search::{PvLine, SearchEngine, SearchLimits, SearchResult};
+pub use state::{FenError, State};
+pub use uci::{find_legal_mov, process_uci_cmd, resolve_searchmoves, Rawmov, UciError, UciProcessResult};
+pub use util::{decode_u32_mov, format_mov};
@@ -1,108 +1,164 @@
-mod def;
-mod eval;
-mod mov_gen;
-mod mov_tbl;
-mod search;
-mod state;
-mod uci;
-mod util;
-
-use state::State;
-use search::SearchEngine;
-use uci::{UciProcessResult, Rawmov};
+#[cfg(feature = "bitboard")]
+use foxsee::{bitboard_divide, BitboardMoveGenerator, BitboardState};
+
+use foxsee::{
+    decode_u32_mov,
+    find_legal_mov,
+    format_mov,
+    process_uci_cmd,
+    resolve_searchmoves,
+    MoveGenerator,
+    SearchEngine,
+    State,
+    UciProcessResult,
+};
 
 use std::io::{self, prelude::*};
+use std::sync::{
+    Arc,
+    Mutex,
+    MutexGuard,
+    PoisonError,
+    atomic::{AtomicBool, Ordering},
+};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
 const FEN_START_POS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
-const OVERHEAD_TIME: u128 = 50;
+const STOP_POLL_INTERVAL_MILLIS: u64 = 5;
+const SEARCH_STACK_SIZE: usize = 32 * 1024 * 1024;
+const MAX_READ_RETRY_COUNT: usize = 3;
+const NULL_MOV_STR: &str = "0000";
 
 fn main() {
-    let mut search_engine = SearchEngine::new();
+    let search_engine = SearchEngine::default();
+    let stop_signal = search_engine.get_stop_signal();
+    let ponder_signal = search_engine.get_ponder_signal();
+    let search_engine = Arc::new(Mutex::new(search_engine));
+    let mov_generator = MoveGenerator::new();
+    let mut search_task = None;
     let mut state = State::new(FEN_START_POS);
 
     loop {
-        let input_cmd = read_gui_input();
-        let uci_cmd_process_result = uci::process_uci_cmd(input_cmd.trim());
+        let uci_cmd_process_result = match read_gui_input() {
+            Some(input_cmd) => process_uci_cmd(input_cmd.trim()),
+            None => UciProcessResult::Quit,
+        };
         match uci_cmd_process_result {
-            UciProcessResult::Position(mov_list) => {
-                state = State::new(FEN_START_POS);
-
-                if mov_list.is_empty() {
-                    continue
+            UciProcessResult::Position(fen, mov_list) => {
+                stop_search(&stop_signal, &mut search_task);
+                let mut next_state = State::new(fen.as_deref().unwrap_or(FEN_START_POS));
+
+                let mut mov_error = None;
+                for raw_mov in &mov_list {
+                    match find_legal_mov(&next_state, &mov_generator, raw_mov) {
+                        Ok(mov) => {
+                            let (from, to, tp, promo) = decode_u32_mov(mov);
+                            next_state.do_mov(from, to, tp, promo);
+                        },
+                        Err(error) => {
+                            mov_error = Some(error);
+                            break
+                        },
+                    }
                 }
 
-                for Rawmov { from, to, promo, origin_mov_str } in mov_list {
-                    if !promo.is_empty() {
-                        let promo_piece_code = if state.player == def::PLAYER_W {
-                            match promo.as_str() {
-                                "q" => def::WQ,
-                                "r" => def::WR,
-                                "b" => def::WB,
-                                "n" => def::WN,
-                                _ => panic!("invalid promo piece"),
-                            }
-                        } else {
-                            match promo.as_str() {
-                                "q" => def::BQ,
-                                "r" => def::BR,
-                                "b" => def::BB,
-                                "n" => def::BN,
-                                _ => panic!("invalid promo piece"),
-                            }
-                        };
-
-                        state.do_mov(from, to, def::MOV_PROMO, promo_piece_code);
+                match mov_error {
+                    Some(error) => {
+                        println!("info string {}, position ignored", error);
+                        io::stdout().flush().ok();
+                    },
+                    None => state = next_state,
+                }
+            },
+            UciProcessResult::StartSearch(mut search_limits, searchmov_list) => {
+                stop_search(&stop_signal, &mut search_task);
+
+                match resolve_searchmoves(&state, &mov_generator, &searchmov_list) {
+                    Ok(searchmoves) => search_limits.searchmoves = searchmoves,
+                    Err(error) => {
+                        println!("info string {}, search ignored", error);
+                        io::stdout().flush().ok();
                         continue
-                    }
+                    },
+                }
 
-                    let moving_piece = state.squares[from];
-                    if def::is_k(moving_piece) && ["e1g1", "e1c1", "e8g8", "e8c8"].contains(&origin_mov_str.as_str()) {
-                        state.do_mov(from, to, def::MOV_CAS, 0);
-                        continue
-                    }
+                stop_signal.store(false, Ordering::Relaxed);
+                ponder_signal.store(search_limits.ponder, Ordering::Relaxed);
 
-                    if def::is_p(moving_piece) {
-                        if to == state.enp_square {
-                            state.do_mov(from, to, def::MOV_ENP, 0);
-                            continue
-                        }
+                let search_engine = search_engine.clone();
+                let stop_signal = stop_signal.clone();
+                let ponder_signal = ponder_signal.clone();
+                let mut state = state.clone();
 
-                        if (from as isize - to as isize).abs() == 32 {
-                            state.do_mov(from, to, def::MOV_CR_ENP, 0);
-                            continue
-                        }
+                let spawn_result = thread::Builder::new().stack_size(SEARCH_STACK_SIZE).spawn(move || {
+                    let search_result = lock_search_engine(&search_engine).search(&mut state, &search_limits);
+
+                    while !stop_signal.load(Ordering::Relaxed) && (search_limits.infinite || ponder_signal.load(Ordering::Relaxed)) {
+                        thread::sleep(Duration::from_millis(STOP_POLL_INTERVAL_MILLIS));
                     }
 
-                    state.do_mov(from, to, def::MOV_REG, 0);
+                    if search_result.best_mov == 0 {
+                        println!("bestmove {}", NULL_MOV_STR);
+                    } else if search_result.ponder_mov != 0 {
+                        println!("bestmove {} ponder {}", format_mov(search_result.best_mov), format_mov(search_result.ponder_mov));
+                    } else {
+                        println!("bestmove {}", format_mov(search_result.best_mov));
+                    }
+                    io::stdout().flush().ok();
+                });
+
+                match spawn_result {
+                    Ok(join_handle) => search_task = Some(join_handle),
+                    Err(error) => {
+                        println!("info string unable to start search {}", error);
+                        io::stdout().flush().ok();
+                    },
                 }
             },
-            UciProcessResult::StartSearchWithTime(time_millis) => {
-                let best_mov = search_engine.search(&mut state, time_millis - OVERHEAD_TIME);
-                println!("bestmove {}", util::format_mov(best_mov));
-                io::stdout().flush().ok();
+            UciProcessResult::SetOption(engine_option) => {
+                stop_search(&stop_signal, &mut search_task);
+                lock_search_engine(&search_engine).set_option(engine_option);
+            },
+            UciProcessResult::PonderHit => {
+                ponder_signal.store(false, Ordering::Relaxed);
+            },
+            UciProcessResult::Perft(depth) => {
+                stop_search(&stop_signal, &mut search_task);
+
+                #[cfg(feature = "bitboard")]
+                let divide_list = bitboard_divide(&BitboardState::from_state(&state), &BitboardMoveGenerator::new(), depth);
 
-                let (from, to, tp, promo) = util::decode_u32_mov(best_mov);
-                state.do_mov(from, to, tp, promo);
+                #[cfg(not(feature = "bitboard"))]
+                let divide_list = foxsee::divide(&mut state, &mov_generator, depth);
+
+                let mut node_count = 0;
+                for (mov, mov_node_count) in divide_list {
+                    println!("{}: {}", format_mov(mov), mov_node_count);
+                    node_count += mov_node_count;
+                }
+
+                println!();
+                println!("Nodes searched: {}", node_count);
+                io::stdout().flush().ok();
             },
-            UciProcessResult::StartSearchWithComplextTimeControl(time_info) => {
-                let time_millis = if state.player == def::PLAYER_W {
-                    time_info.white_millis
-                } else {
-                    time_info.black_millis
-                };
-
-                let best_mov = search_engine.search(&mut state, time_millis - OVERHEAD_TIME);
-                println!("bestmove {}", util::format_mov(best_mov));
+            UciProcessResult::Display => {
+                println!("{}", state);
+                println!("Fen: {}", state.to_fen());
+                println!("Key: {:016X}", state.get_hash_key());
                 io::stdout().flush().ok();
-
-                let (from, to, tp, promo) = util::decode_u32_mov(best_mov);
-                state.do_mov(from, to, tp, promo);
             },
             UciProcessResult::Ready => {},
-            UciProcessResult::Stop => {},
+            UciProcessResult::Stop => {
+                stop_signal.store(true, Ordering::Relaxed);
+            },
             UciProcessResult::Noop => {},
-            UciProcessResult::Reset => {},
+            UciProcessResult::Reset => {
+                stop_search(&stop_signal, &mut search_task);
+                lock_search_engine(&search_engine).reset();
+            },
             UciProcessResult::Quit => {
+                stop_search(&stop_signal, &mut search_task);
                 println!("quit");
                 std::process::exit(0);
             }
@@ -110,10 +166,33 @@ fn main() {
     }
 }
 
-fn read_gui_input() -> String {
-    let mut input = String::new();
-    match io::stdin().read_line(&mut input) {
-        Ok(_) => input,
-        Err(error) => panic!("uable to read input {}", error),
+fn stop_search(stop_signal: &AtomicBool, search_task: &mut Option<JoinHandle<()>>) {
+    if let Some(search_task) = search_task.take() {
+        stop_signal.store(true, Ordering::Relaxed);
+        if search_task.join().is_err() {
+            println!("info string search thread panicked");
+            io::stdout().flush().ok();
+        }
     }
 }
+
+// a panicked search leaves the mutex poisoned, the engine itself is still usable
+fn lock_search_engine(search_engine: &Mutex<SearchEngine>) -> MutexGuard<'_, SearchEngine> {
+    search_engine.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn read_gui_input() -> Option<String> {
+    for _ in 0..MAX_READ_RETRY_COUNT {
+        let mut input = String::new();
+        match io::stdin().read_line(&mut input) {
+            Ok(0) => return None,
+            Ok(_) => return Some(input),
+            Err(error) => {
+                println!("info string unable to read input {}", error);
+                io::stdout().flush().ok();
+            },
+        }
+    }
+
+    None
+}
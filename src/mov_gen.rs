@@ -1,117 +1,306 @@
+use std::ops::{Deref, DerefMut};
+
 use crate::{
     def,
+    mov_list::MoveList,
     mov_tbl,
     state::State,
     util,
 };
 
-pub struct MoveGenerator {
-    k_mov_table: Vec<Vec<usize>>,
-    n_mov_table: Vec<Vec<usize>>,
+const MAX_ATTACKER_COUNT: usize = 32;
 
-    up_mov_table: Vec<Vec<usize>>,
-    down_mov_table: Vec<Vec<usize>>,
-    left_mov_table: Vec<Vec<usize>>,
-    right_mov_table: Vec<Vec<usize>>,
+#[derive(Clone, Copy)]
+pub struct AttackerList {
+    attacker_list: [u8; MAX_ATTACKER_COUNT],
+    attacker_count: usize,
+}
 
-    up_left_mov_table: Vec<Vec<usize>>,
-    up_right_mov_table: Vec<Vec<usize>>,
-    down_right_mov_table: Vec<Vec<usize>>,
-    down_left_mov_table: Vec<Vec<usize>>,
+impl Default for AttackerList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AttackerList {
+    pub fn new() -> AttackerList {
+        AttackerList {
+            attacker_list: [0; MAX_ATTACKER_COUNT],
+            attacker_count: 0,
+        }
+    }
+
+    #[inline]
+    pub fn push(&mut self, attacker: u8) {
+        self.attacker_list[self.attacker_count] = attacker;
+        self.attacker_count += 1;
+    }
+}
+
+impl Deref for AttackerList {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        &self.attacker_list[..self.attacker_count]
+    }
 }
 
+impl DerefMut for AttackerList {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.attacker_list[..self.attacker_count]
+    }
+}
+
+#[derive(Default)]
+pub struct MoveGenerator;
+
 impl MoveGenerator {
     pub fn new() -> MoveGenerator {
-        MoveGenerator {
-            k_mov_table: mov_tbl::gen_k_mov_table(),
-            n_mov_table: mov_tbl::gen_n_mov_table(),
-
-            up_mov_table: mov_tbl::gen_up_slide_mov_table(),
-            down_mov_table: mov_tbl::gen_down_slide_mov_table(),
-            left_mov_table: mov_tbl::gen_left_slide_mov_table(),
-            right_mov_table: mov_tbl::gen_right_slide_mov_table(),
-
-            up_left_mov_table: mov_tbl::gen_up_left_slide_mov_table(),
-            up_right_mov_table: mov_tbl::gen_up_right_slide_mov_table(),
-            down_right_mov_table: mov_tbl::gen_down_right_slide_mov_table(),
-            down_left_mov_table: mov_tbl::gen_down_left_slide_mov_table(),
-        }
+        MoveGenerator
     }
 
-    pub fn gen_castle_mov_list(&self, state: &State) -> Vec<u32> {
+    pub fn gen_castle_mov_list(&self, state: &State) -> MoveList {
         let cas_rights = state.cas_rights;
         let squares = state.squares;
 
-        let mut mov_list = Vec::new();
+        let mut mov_list = MoveList::new();
 
         if state.player == def::PLAYER_W {
-            if cas_rights & 0b1000 != 0 {
-                if squares[def::CAS_SQUARE_WK - 2] == def::WK
+            if cas_rights & 0b1000 != 0
+                && squares[def::CAS_SQUARE_WK - 2] == def::WK
                 && squares[def::CAS_SQUARE_WK + 1] == def::WR
                 && squares[def::CAS_SQUARE_WK] == 0
                 && squares[def::CAS_SQUARE_WK - 1] == 0
                 && !self.is_under_attack(state, def::CAS_SQUARE_WK)
                 && !self.is_under_attack(state, def::CAS_SQUARE_WK - 1)
                 && !self.is_under_attack(state, def::CAS_SQUARE_WK - 2) {
-                    mov_list.push(util::encode_u32_mov(def::CAS_SQUARE_WK - 2, def::CAS_SQUARE_WK, def::MOV_CAS, 0));
-                }
+                mov_list.push(util::encode_u32_mov(def::CAS_SQUARE_WK - 2, def::CAS_SQUARE_WK, def::MOV_CAS, 0));
             }
         
-            if cas_rights & 0b0100 != 0 {
-                if squares[def::CAS_SQUARE_WQ + 2] == def::WK
+            if cas_rights & 0b0100 != 0
+                && squares[def::CAS_SQUARE_WQ + 2] == def::WK
                 && squares[def::CAS_SQUARE_WQ - 2] == def::WR
+                && squares[def::CAS_SQUARE_WQ - 1] == 0
                 && squares[def::CAS_SQUARE_WQ] == 0
                 && squares[def::CAS_SQUARE_WQ + 1] == 0
                 && !self.is_under_attack(state, def::CAS_SQUARE_WQ)
                 && !self.is_under_attack(state, def::CAS_SQUARE_WQ + 1)
                 && !self.is_under_attack(state, def::CAS_SQUARE_WQ + 2) {
-                    mov_list.push(util::encode_u32_mov(def::CAS_SQUARE_WQ + 2, def::CAS_SQUARE_WQ, def::MOV_CAS, 0));
-                }
+                mov_list.push(util::encode_u32_mov(def::CAS_SQUARE_WQ + 2, def::CAS_SQUARE_WQ, def::MOV_CAS, 0));
             }
         } else {
-            if cas_rights & 0b0010 != 0 {
-                if squares[def::CAS_SQUARE_BK - 2] == def::BK
+            if cas_rights & 0b0010 != 0
+                && squares[def::CAS_SQUARE_BK - 2] == def::BK
                 && squares[def::CAS_SQUARE_BK + 1] == def::BR
                 && squares[def::CAS_SQUARE_BK] == 0
                 && squares[def::CAS_SQUARE_BK - 1] == 0
                 && !self.is_under_attack(state, def::CAS_SQUARE_BK)
                 && !self.is_under_attack(state, def::CAS_SQUARE_BK - 1)
                 && !self.is_under_attack(state, def::CAS_SQUARE_BK - 2) {
-                    mov_list.push(util::encode_u32_mov(def::CAS_SQUARE_BK - 2, def::CAS_SQUARE_BK, def::MOV_CAS, 0));
-                }
+                mov_list.push(util::encode_u32_mov(def::CAS_SQUARE_BK - 2, def::CAS_SQUARE_BK, def::MOV_CAS, 0));
             }
         
-            if cas_rights & 0b0001 != 0 {
-                if squares[def::CAS_SQUARE_BQ + 2] == def::BK
+            if cas_rights & 0b0001 != 0
+                && squares[def::CAS_SQUARE_BQ + 2] == def::BK
                 && squares[def::CAS_SQUARE_BQ - 2] == def::BR
+                && squares[def::CAS_SQUARE_BQ - 1] == 0
                 && squares[def::CAS_SQUARE_BQ] == 0
                 && squares[def::CAS_SQUARE_BQ + 1] == 0
                 && !self.is_under_attack(state, def::CAS_SQUARE_BQ)
                 && !self.is_under_attack(state, def::CAS_SQUARE_BQ + 1)
                 && !self.is_under_attack(state, def::CAS_SQUARE_BQ + 2) {
-                    mov_list.push(util::encode_u32_mov(def::CAS_SQUARE_BQ + 2, def::CAS_SQUARE_BQ, def::MOV_CAS, 0));
+                mov_list.push(util::encode_u32_mov(def::CAS_SQUARE_BQ + 2, def::CAS_SQUARE_BQ, def::MOV_CAS, 0));
+            }
+        }
+
+        mov_list
+    }
+
+    pub fn gen_legal_mov_list(&self, state: &State) -> MoveList {
+        let player = state.player;
+        let squares = &state.squares;
+
+        let k_index = if player == def::PLAYER_W {
+            state.wk_index
+        } else {
+            state.bk_index
+        };
+
+        let mut check_count = 0;
+        let mut evasion_sqr_list = [false; def::BOARD_SIZE];
+        let mut pin_ray_list: [Option<&[usize]>; def::BOARD_SIZE] = [None; def::BOARD_SIZE];
+
+        let ray_table_list = [
+            (&mov_tbl::UP_MOV_TABLE, false),
+            (&mov_tbl::DOWN_MOV_TABLE, false),
+            (&mov_tbl::LEFT_MOV_TABLE, false),
+            (&mov_tbl::RIGHT_MOV_TABLE, false),
+            (&mov_tbl::UP_LEFT_MOV_TABLE, true),
+            (&mov_tbl::UP_RIGHT_MOV_TABLE, true),
+            (&mov_tbl::DOWN_RIGHT_MOV_TABLE, true),
+            (&mov_tbl::DOWN_LEFT_MOV_TABLE, true),
+        ];
+
+        for (ray_table, is_diagonal) in ray_table_list.iter() {
+            let ray = ray_table.get(k_index);
+            let mut blocker_index = None;
+
+            for (ray_index, to_index) in ray.iter().enumerate() {
+                let piece = squares[*to_index];
+
+                if piece == 0 {
+                    continue
+                }
+
+                if def::on_same_side(player, piece) {
+                    if blocker_index.is_some() {
+                        break
+                    }
+
+                    blocker_index = Some(*to_index);
+                    continue
+                }
+
+                let is_slider = if *is_diagonal {
+                    def::is_b(piece) || def::is_q(piece)
+                } else {
+                    def::is_r(piece) || def::is_q(piece)
+                };
+
+                if is_slider {
+                    match blocker_index {
+                        Some(blocker_index) => pin_ray_list[blocker_index] = Some(ray),
+                        None => {
+                            check_count += 1;
+
+                            for evasion_index in &ray[0..=ray_index] {
+                                evasion_sqr_list[*evasion_index] = true;
+                            }
+                        },
+                    }
+                }
+
+                break
+            }
+        }
+
+        for to_index in mov_tbl::N_MOV_TABLE.get(k_index) {
+            let piece = squares[*to_index];
+
+            if def::is_n(piece) && !def::on_same_side(player, piece) {
+                check_count += 1;
+                evasion_sqr_list[*to_index] = true;
+            }
+        }
+
+        let pawn_attacker_index_list = if player == def::PLAYER_W {
+            [k_index + 15, k_index + 17]
+        } else {
+            [k_index.wrapping_sub(15), k_index.wrapping_sub(17)]
+        };
+
+        for pawn_index in pawn_attacker_index_list.iter() {
+            let pawn_index = *pawn_index;
+
+            if def::is_index_valid(pawn_index) {
+                let piece = squares[pawn_index];
+
+                if def::is_p(piece) && !def::on_same_side(player, piece) {
+                    check_count += 1;
+                    evasion_sqr_list[pawn_index] = true;
+                }
+            }
+        }
+
+        let (cap_list, non_cap_list) = self.gen_reg_mov_list(state, false);
+        let mut mov_list = MoveList::new();
+
+        for mov in cap_list.iter().chain(non_cap_list.iter()) {
+            let mov = *mov;
+            let (from, to, tp, _promo) = util::decode_u32_mov(mov);
+
+            if from == k_index {
+                let mut squares_without_k = *squares;
+                squares_without_k[k_index] = 0;
+
+                if !self.is_sqr_under_attack(&squares_without_k, player, to) {
+                    mov_list.push(mov);
+                }
+
+                continue
+            }
+
+            if check_count > 1 {
+                continue
+            }
+
+            if tp == def::MOV_ENP {
+                let taken_index = if player == def::PLAYER_W {
+                    to - 16
+                } else {
+                    to + 16
+                };
+
+                let mut squares_after_enp = *squares;
+                squares_after_enp[to] = squares[from];
+                squares_after_enp[from] = 0;
+                squares_after_enp[taken_index] = 0;
+
+                if !self.is_sqr_under_attack(&squares_after_enp, player, k_index) {
+                    mov_list.push(mov);
                 }
+
+                continue
+            }
+
+            if check_count == 1 && !evasion_sqr_list[to] {
+                continue
             }
+
+            if let Some(pin_ray) = pin_ray_list[from] {
+                if !pin_ray.contains(&to) {
+                    continue
+                }
+            }
+
+            mov_list.push(mov);
+        }
+
+        if check_count == 0 {
+            mov_list.extend_from_slice(&self.gen_castle_mov_list(state));
         }
 
         mov_list
     }
 
-    pub fn gen_reg_mov_list(&self, state: &State, capture_only: bool) -> (Vec<u32>, Vec<u32>) {
+    pub fn gen_reg_mov_list(&self, state: &State, capture_only: bool) -> (MoveList, MoveList) {
+        self.gen_reg_mov_list_by_type(state, true, !capture_only)
+    }
+
+    pub fn gen_non_cap_mov_list(&self, state: &State) -> MoveList {
+        let (_cap_list, non_cap_list) = self.gen_reg_mov_list_by_type(state, false, true);
+        non_cap_list
+    }
+
+    fn gen_reg_mov_list_by_type(&self, state: &State, gen_cap: bool, gen_non_cap: bool) -> (MoveList, MoveList) {
         let squares = state.squares;
         let player = state.player;
 
-        let mut mov_list = Vec::new();
-        let mut cap_list = Vec::new();
+        let mut mov_list = MoveList::new();
+        let mut cap_list = MoveList::new();
     
         let mut add_mov = |from: usize, to: usize, tp: u8, promo: u8| {
-            if !capture_only {
+            if gen_non_cap {
                 mov_list.push(util::encode_u32_mov(from, to, tp, promo));
             }
         };
     
         let mut add_cap = |from: usize, to: usize, tp: u8, promo: u8| {
-            cap_list.push(util::encode_u32_mov(from, to, tp, promo));
+            if gen_cap {
+                cap_list.push(util::encode_u32_mov(from, to, tp, promo));
+            }
         };
 
         let mut from_index = 0;
@@ -228,7 +417,7 @@ impl MoveGenerator {
                             }
                         }
     
-                        let take_index = from_index - 17;
+                        let take_index = from_index.wrapping_sub(17);
                         if def::is_index_valid(take_index) {
                             let take = squares[take_index];
                             if take != 0 && !def::on_same_side(player, take) {
@@ -249,7 +438,7 @@ impl MoveGenerator {
                     }
                 }
             } else if def::is_n(moving_piece) {
-                let mov_index_list = &self.n_mov_table[from_index];
+                let mov_index_list = mov_tbl::N_MOV_TABLE.get(from_index);
                 for to_index in mov_index_list {
                     let to_index = *to_index;
                     let taken_piece = squares[to_index];
@@ -264,7 +453,7 @@ impl MoveGenerator {
                     }
                 }
             } else if def::is_b(moving_piece) {
-                let mov_index_list = &self.up_left_mov_table[from_index];
+                let mov_index_list = mov_tbl::UP_LEFT_MOV_TABLE.get(from_index);
                 for to_index in mov_index_list {
                     let to_index = *to_index;
                     let taken_piece = squares[to_index];
@@ -281,7 +470,7 @@ impl MoveGenerator {
                     break
                 }
 
-                let mov_index_list = &self.up_right_mov_table[from_index];
+                let mov_index_list = mov_tbl::UP_RIGHT_MOV_TABLE.get(from_index);
                 for to_index in mov_index_list {
                     let to_index = *to_index;
                     let taken_piece = squares[to_index];
@@ -298,7 +487,7 @@ impl MoveGenerator {
                     break
                 }
 
-                let mov_index_list = &self.down_right_mov_table[from_index];
+                let mov_index_list = mov_tbl::DOWN_RIGHT_MOV_TABLE.get(from_index);
                 for to_index in mov_index_list {
                     let to_index = *to_index;
                     let taken_piece = squares[to_index];
@@ -315,7 +504,7 @@ impl MoveGenerator {
                     break
                 }
 
-                let mov_index_list = &self.down_left_mov_table[from_index];
+                let mov_index_list = mov_tbl::DOWN_LEFT_MOV_TABLE.get(from_index);
                 for to_index in mov_index_list {
                     let to_index = *to_index;
                     let taken_piece = squares[to_index];
@@ -332,7 +521,7 @@ impl MoveGenerator {
                     break
                 }
             } else if def::is_r(moving_piece) {
-                let mov_index_list = &self.up_mov_table[from_index];
+                let mov_index_list = mov_tbl::UP_MOV_TABLE.get(from_index);
                 for to_index in mov_index_list {
                     let to_index = *to_index;
                     let taken_piece = squares[to_index];
@@ -349,7 +538,7 @@ impl MoveGenerator {
                     break
                 }
 
-                let mov_index_list = &self.right_mov_table[from_index];
+                let mov_index_list = mov_tbl::RIGHT_MOV_TABLE.get(from_index);
                 for to_index in mov_index_list {
                     let to_index = *to_index;
                     let taken_piece = squares[to_index];
@@ -366,7 +555,7 @@ impl MoveGenerator {
                     break
                 }
 
-                let mov_index_list = &self.down_mov_table[from_index];
+                let mov_index_list = mov_tbl::DOWN_MOV_TABLE.get(from_index);
                 for to_index in mov_index_list {
                     let to_index = *to_index;
                     let taken_piece = squares[to_index];
@@ -383,7 +572,7 @@ impl MoveGenerator {
                     break
                 }
 
-                let mov_index_list = &self.left_mov_table[from_index];
+                let mov_index_list = mov_tbl::LEFT_MOV_TABLE.get(from_index);
                 for to_index in mov_index_list {
                     let to_index = *to_index;
                     let taken_piece = squares[to_index];
@@ -400,7 +589,7 @@ impl MoveGenerator {
                     break
                 }
             } else if def::is_q(moving_piece) {
-                let mov_index_list = &self.up_left_mov_table[from_index];
+                let mov_index_list = mov_tbl::UP_LEFT_MOV_TABLE.get(from_index);
                 for to_index in mov_index_list {
                     let to_index = *to_index;
                     let taken_piece = squares[to_index];
@@ -417,7 +606,7 @@ impl MoveGenerator {
                     break
                 }
 
-                let mov_index_list = &self.up_right_mov_table[from_index];
+                let mov_index_list = mov_tbl::UP_RIGHT_MOV_TABLE.get(from_index);
                 for to_index in mov_index_list {
                     let to_index = *to_index;
                     let taken_piece = squares[to_index];
@@ -434,7 +623,7 @@ impl MoveGenerator {
                     break
                 }
 
-                let mov_index_list = &self.down_right_mov_table[from_index];
+                let mov_index_list = mov_tbl::DOWN_RIGHT_MOV_TABLE.get(from_index);
                 for to_index in mov_index_list {
                     let to_index = *to_index;
                     let taken_piece = squares[to_index];
@@ -451,7 +640,7 @@ impl MoveGenerator {
                     break
                 }
 
-                let mov_index_list = &self.down_left_mov_table[from_index];
+                let mov_index_list = mov_tbl::DOWN_LEFT_MOV_TABLE.get(from_index);
                 for to_index in mov_index_list {
                     let to_index = *to_index;
                     let taken_piece = squares[to_index];
@@ -468,7 +657,7 @@ impl MoveGenerator {
                     break
                 }
 
-                let mov_index_list = &self.up_mov_table[from_index];
+                let mov_index_list = mov_tbl::UP_MOV_TABLE.get(from_index);
                 for to_index in mov_index_list {
                     let to_index = *to_index;
                     let taken_piece = squares[to_index];
@@ -485,7 +674,7 @@ impl MoveGenerator {
                     break
                 }
 
-                let mov_index_list = &self.right_mov_table[from_index];
+                let mov_index_list = mov_tbl::RIGHT_MOV_TABLE.get(from_index);
                 for to_index in mov_index_list {
                     let to_index = *to_index;
                     let taken_piece = squares[to_index];
@@ -502,7 +691,7 @@ impl MoveGenerator {
                     break
                 }
 
-                let mov_index_list = &self.down_mov_table[from_index];
+                let mov_index_list = mov_tbl::DOWN_MOV_TABLE.get(from_index);
                 for to_index in mov_index_list {
                     let to_index = *to_index;
                     let taken_piece = squares[to_index];
@@ -519,7 +708,7 @@ impl MoveGenerator {
                     break
                 }
 
-                let mov_index_list = &self.left_mov_table[from_index];
+                let mov_index_list = mov_tbl::LEFT_MOV_TABLE.get(from_index);
                 for to_index in mov_index_list {
                     let to_index = *to_index;
                     let taken_piece = squares[to_index];
@@ -536,7 +725,7 @@ impl MoveGenerator {
                     break
                 }
             } else if def::is_k(moving_piece) {
-                let mov_index_list = &self.k_mov_table[from_index];
+                let mov_index_list = mov_tbl::K_MOV_TABLE.get(from_index);
                 for to_index in mov_index_list {
                     let to_index = *to_index;
                     let taken_piece = squares[to_index];
@@ -558,6 +747,112 @@ impl MoveGenerator {
         (cap_list, mov_list)
     }
 
+    pub fn is_mov_valid(&self, state: &State, mov: u32) -> bool {
+        let (from, to, tp, promo) = util::decode_u32_mov(mov);
+
+        if !def::is_index_valid(from) || !def::is_index_valid(to) {
+            return false
+        }
+
+        if tp == def::MOV_CAS {
+            return self.gen_castle_mov_list(state).contains(&mov)
+        }
+
+        let squares = state.squares;
+        let player = state.player;
+        let moving_piece = squares[from];
+        let taken_piece = squares[to];
+
+        if moving_piece == 0 || !def::on_same_side(player, moving_piece) {
+            return false
+        }
+
+        if taken_piece != 0 && def::on_same_side(player, taken_piece) {
+            return false
+        }
+
+        if def::is_p(moving_piece) {
+            let (forward, is_promo_rank, is_start_rank) = if player == def::PLAYER_W {
+                (16, to > 111, from < 24)
+            } else {
+                (-16, to < 8, from > 95)
+            };
+
+            let is_promo_valid = if is_promo_rank {
+                tp == def::MOV_PROMO && promo != 0 && def::on_same_side(player, promo) && !def::is_p(promo) && !def::is_k(promo)
+            } else {
+                tp == def::MOV_REG && promo == 0
+            };
+
+            let mov_distance = to as isize - from as isize;
+
+            if mov_distance == forward {
+                return taken_piece == 0 && is_promo_valid
+            }
+
+            if mov_distance == forward * 2 {
+                return tp == def::MOV_CR_ENP && is_start_rank && taken_piece == 0 && squares[(from as isize + forward) as usize] == 0
+            }
+
+            if mov_distance == forward - 1 || mov_distance == forward + 1 {
+                if tp == def::MOV_ENP {
+                    return state.enp_square != 0 && to == state.enp_square && taken_piece == 0
+                }
+
+                return taken_piece != 0 && is_promo_valid
+            }
+
+            return false
+        }
+
+        if tp != def::MOV_REG || promo != 0 {
+            return false
+        }
+
+        if def::is_n(moving_piece) {
+            return mov_tbl::N_MOV_TABLE.get(from).contains(&to)
+        }
+
+        if def::is_k(moving_piece) {
+            return mov_tbl::K_MOV_TABLE.get(from).contains(&to)
+        }
+
+        let slide_mov_table_list = [
+            (&mov_tbl::UP_LEFT_MOV_TABLE, true),
+            (&mov_tbl::UP_RIGHT_MOV_TABLE, true),
+            (&mov_tbl::DOWN_RIGHT_MOV_TABLE, true),
+            (&mov_tbl::DOWN_LEFT_MOV_TABLE, true),
+            (&mov_tbl::UP_MOV_TABLE, false),
+            (&mov_tbl::RIGHT_MOV_TABLE, false),
+            (&mov_tbl::DOWN_MOV_TABLE, false),
+            (&mov_tbl::LEFT_MOV_TABLE, false),
+        ];
+
+        for (mov_table, is_diagonal) in slide_mov_table_list.iter() {
+            let is_slider = if *is_diagonal {
+                def::is_b(moving_piece) || def::is_q(moving_piece)
+            } else {
+                def::is_r(moving_piece) || def::is_q(moving_piece)
+            };
+
+            if !is_slider {
+                continue
+            }
+
+            for index in mov_table.get(from) {
+                if *index == to {
+                    return true
+                }
+
+                if squares[*index] != 0 {
+                    break
+                }
+            }
+        }
+
+        false
+    }
+
     pub fn is_in_check(&self, state: &State) -> bool {
         let k_index = if state.player == def::PLAYER_W {
             state.wk_index
@@ -569,21 +864,21 @@ impl MoveGenerator {
     }
 
     pub fn is_under_attack(&self, state: &State, index: usize) -> bool {
-        let player = state.player;
-        let squares = state.squares;
+        self.is_sqr_under_attack(&state.squares, state.player, index)
+    }
+
+    pub fn is_sqr_under_attack(&self, squares: &[u8; def::BOARD_SIZE], player: u8, index: usize) -> bool {
 
-        let mov_index_list = &self.n_mov_table[index];
+        let mov_index_list = mov_tbl::N_MOV_TABLE.get(index);
         for to_index in mov_index_list {
             let taken_piece = squares[*to_index];
 
-            if taken_piece != 0 {
-                if !def::on_same_side(player, taken_piece) && def::is_n(taken_piece) {
-                    return true
-                }
+            if taken_piece != 0 && !def::on_same_side(player, taken_piece) && def::is_n(taken_piece) {
+                return true
             }
         }
 
-        let mov_index_list = &self.up_mov_table[index];
+        let mov_index_list = mov_tbl::UP_MOV_TABLE.get(index);
         for to_index in mov_index_list {
             let taken_piece = squares[*to_index];
 
@@ -600,7 +895,7 @@ impl MoveGenerator {
             }
         }
 
-        let mov_index_list = &self.down_mov_table[index];
+        let mov_index_list = mov_tbl::DOWN_MOV_TABLE.get(index);
         for to_index in mov_index_list {
             let taken_piece = squares[*to_index];
 
@@ -617,7 +912,7 @@ impl MoveGenerator {
             }
         }
 
-        let mov_index_list = &self.left_mov_table[index];
+        let mov_index_list = mov_tbl::LEFT_MOV_TABLE.get(index);
         for to_index in mov_index_list {
             let taken_piece = squares[*to_index];
 
@@ -634,7 +929,7 @@ impl MoveGenerator {
             }
         }
 
-        let mov_index_list = &self.right_mov_table[index];
+        let mov_index_list = mov_tbl::RIGHT_MOV_TABLE.get(index);
         for to_index in mov_index_list {
             let taken_piece = squares[*to_index];
 
@@ -651,7 +946,7 @@ impl MoveGenerator {
             }
         }
 
-        let mov_index_list = &self.up_left_mov_table[index];
+        let mov_index_list = mov_tbl::UP_LEFT_MOV_TABLE.get(index);
         for to_index in mov_index_list {
             let taken_piece = squares[*to_index];
 
@@ -668,7 +963,7 @@ impl MoveGenerator {
             }
         }
 
-        let mov_index_list = &self.up_right_mov_table[index];
+        let mov_index_list = mov_tbl::UP_RIGHT_MOV_TABLE.get(index);
         for to_index in mov_index_list {
             let taken_piece = squares[*to_index];
 
@@ -685,7 +980,7 @@ impl MoveGenerator {
             }
         }
 
-        let mov_index_list = &self.down_right_mov_table[index];
+        let mov_index_list = mov_tbl::DOWN_RIGHT_MOV_TABLE.get(index);
         for to_index in mov_index_list {
             let taken_piece = squares[*to_index];
 
@@ -702,7 +997,7 @@ impl MoveGenerator {
             }
         }
 
-        let mov_index_list = &self.down_left_mov_table[index];
+        let mov_index_list = mov_tbl::DOWN_LEFT_MOV_TABLE.get(index);
         for to_index in mov_index_list {
             let taken_piece = squares[*to_index];
 
@@ -754,26 +1049,24 @@ impl MoveGenerator {
             }
         }
 
-        let mov_index_list = &self.k_mov_table[index];
+        let mov_index_list = mov_tbl::K_MOV_TABLE.get(index);
         for to_index in mov_index_list {
             let taken_piece = squares[*to_index];
 
-            if taken_piece != 0 {
-                if !def::on_same_side(player, taken_piece) && def::is_k(taken_piece) {
-                    return true
-                }
+            if taken_piece != 0 && !def::on_same_side(player, taken_piece) && def::is_k(taken_piece) {
+                return true
             }
         }
 
         false
     }
 
-    pub fn find_attacker_list(&self, state: &State, index: usize) -> (Vec<u8>, Vec<u8>) {
-        let mut attacker_list = Vec::new();
+    pub fn find_attacker_list(&self, state: &State, index: usize) -> (AttackerList, AttackerList) {
+        let mut attacker_list = AttackerList::new();
 
         let squares = state.squares;
 
-        let mov_index_list = &self.n_mov_table[index];
+        let mov_index_list = mov_tbl::N_MOV_TABLE.get(index);
         for to_index in mov_index_list {
             let attacker_piece = squares[*to_index];
 
@@ -786,7 +1079,7 @@ impl MoveGenerator {
             }
         }
 
-        let mov_index_list = &self.up_left_mov_table[index];
+        let mov_index_list = mov_tbl::UP_LEFT_MOV_TABLE.get(index);
         let mut direction_mov_count = 0;
         for to_index in mov_index_list {
             direction_mov_count += 1;
@@ -809,7 +1102,7 @@ impl MoveGenerator {
             }
         }
 
-        let mov_index_list = &self.up_right_mov_table[index];
+        let mov_index_list = mov_tbl::UP_RIGHT_MOV_TABLE.get(index);
         direction_mov_count = 0;
         for to_index in mov_index_list {
             direction_mov_count += 1;
@@ -832,7 +1125,7 @@ impl MoveGenerator {
             }
         }
 
-        let mov_index_list = &self.down_right_mov_table[index];
+        let mov_index_list = mov_tbl::DOWN_RIGHT_MOV_TABLE.get(index);
         direction_mov_count = 0;
         for to_index in mov_index_list {
             direction_mov_count += 1;
@@ -855,7 +1148,7 @@ impl MoveGenerator {
             }
         }
 
-        let mov_index_list = &self.down_left_mov_table[index];
+        let mov_index_list = mov_tbl::DOWN_LEFT_MOV_TABLE.get(index);
         direction_mov_count = 0;
         for to_index in mov_index_list {
             direction_mov_count += 1;
@@ -878,7 +1171,7 @@ impl MoveGenerator {
             }
         }
 
-        let mov_index_list = &self.up_mov_table[index];
+        let mov_index_list = mov_tbl::UP_MOV_TABLE.get(index);
         direction_mov_count = 0;
         for to_index in mov_index_list {
             direction_mov_count += 1;
@@ -901,7 +1194,7 @@ impl MoveGenerator {
             }
         }
 
-        let mov_index_list = &self.right_mov_table[index];
+        let mov_index_list = mov_tbl::RIGHT_MOV_TABLE.get(index);
         direction_mov_count = 0;
         for to_index in mov_index_list {
             direction_mov_count += 1;
@@ -924,7 +1217,7 @@ impl MoveGenerator {
             }
         }
 
-        let mov_index_list = &self.down_mov_table[index];
+        let mov_index_list = mov_tbl::DOWN_MOV_TABLE.get(index);
         direction_mov_count = 0;
         for to_index in mov_index_list {
             direction_mov_count += 1;
@@ -947,7 +1240,7 @@ impl MoveGenerator {
             }
         }
 
-        let mov_index_list = &self.left_mov_table[index];
+        let mov_index_list = mov_tbl::LEFT_MOV_TABLE.get(index);
         direction_mov_count = 0;
         for to_index in mov_index_list {
             direction_mov_count += 1;
@@ -973,7 +1266,7 @@ impl MoveGenerator {
         if index < 105 && squares[index+15] == def::BP {
             attacker_list.push(def::BP);
 
-            let mov_index_list = &self.up_left_mov_table[index];
+            let mov_index_list = mov_tbl::UP_LEFT_MOV_TABLE.get(index);
             for to_index in mov_index_list {
                 let attacker_piece = squares[*to_index];
 
@@ -992,7 +1285,7 @@ impl MoveGenerator {
         if index < 103 && squares[index+17] == def::BP {
             attacker_list.push(def::BP);
 
-            let mov_index_list = &self.up_right_mov_table[index];
+            let mov_index_list = mov_tbl::UP_RIGHT_MOV_TABLE.get(index);
             for to_index in mov_index_list {
                 let attacker_piece = squares[*to_index];
 
@@ -1011,7 +1304,7 @@ impl MoveGenerator {
         if index >= 15 && squares[index-15] == def::WP {
             attacker_list.push(def::WP);
 
-            let mov_index_list = &self.down_right_mov_table[index];
+            let mov_index_list = mov_tbl::DOWN_RIGHT_MOV_TABLE.get(index);
             for to_index in mov_index_list {
                 let attacker_piece = squares[*to_index];
 
@@ -1030,7 +1323,7 @@ impl MoveGenerator {
         if index >= 17 && squares[index-17] == def::WP {
             attacker_list.push(def::WP);
 
-            let mov_index_list = &self.down_left_mov_table[index];
+            let mov_index_list = mov_tbl::DOWN_LEFT_MOV_TABLE.get(index);
             for to_index in mov_index_list {
                 let attacker_piece = squares[*to_index];
 
@@ -1046,12 +1339,12 @@ impl MoveGenerator {
             }
         }
 
-        attacker_list.sort();
+        attacker_list.sort_unstable();
 
-        let mut w_attacker_list = Vec::new();
-        let mut b_attacker_list = Vec::new();
+        let mut w_attacker_list = AttackerList::new();
+        let mut b_attacker_list = AttackerList::new();
 
-        for a in attacker_list {
+        for a in attacker_list.iter().copied() {
             if a & 1 == 1 {
                 b_attacker_list.push(a);
             } else {
@@ -1091,14 +1384,14 @@ mod tests {
         for c in &cap_list {
             let mov_str = util::format_mov(*c);
             if !expected_cap_list.contains(&&*mov_str) {
-                assert!(false, "{} not matched", mov_str);
+                panic!("{} not matched", mov_str);
             }
         }
 
         for nc in &non_cap_list {
             let mov_str = util::format_mov(*nc);
             if !expected_non_cap_list.contains(&&*mov_str) {
-                assert!(false, "{} not matched", mov_str);
+                panic!("{} not matched", mov_str);
             }
         }
 
@@ -1121,7 +1414,7 @@ mod tests {
         for c in &cas_list {
             let mov_str = util::format_mov(*c);
             if !expected_cas_mov_list.contains(&&*mov_str) {
-                assert!(false, "{} not matched", mov_str);
+                panic!("{} not matched", mov_str);
             }
         }
 
@@ -1302,4 +1595,112 @@ mod tests {
 
         assert!(mov_generator.is_in_check(&state));
     }
+
+    #[test]
+    fn test_mov_valid() {
+        let fen_list = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/Pp2P3/2N2Q1p/1PPBBPPP/R3K2R b KQkq a3 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 b kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ];
+
+        let mov_generator = MoveGenerator::new();
+        let mut state_list = Vec::new();
+        let mut mov_pool = Vec::new();
+
+        for fen in fen_list.iter() {
+            let state = State::new(fen);
+            let (cap_list, non_cap_list) = mov_generator.gen_reg_mov_list(&state, false);
+            assert_eq!(non_cap_list, mov_generator.gen_non_cap_mov_list(&state));
+
+            let mut mov_list = cap_list;
+            mov_list.extend_from_slice(&non_cap_list);
+            mov_list.extend_from_slice(&mov_generator.gen_castle_mov_list(&state));
+
+            mov_pool.extend(mov_list.iter().copied());
+            state_list.push((state, mov_list));
+        }
+
+        for (state, mov_list) in state_list.iter() {
+            for mov in mov_pool.iter() {
+                assert_eq!(mov_list.contains(mov), mov_generator.is_mov_valid(state, *mov), "{} failed for {}", util::format_mov(*mov), state.to_fen());
+            }
+        }
+    }
+
+    fn gen_legal_movs_by_mov_making(state: &mut State, mov_generator: &MoveGenerator) -> Vec<u32> {
+        let (cap_list, non_cap_list) = mov_generator.gen_reg_mov_list(state, false);
+        let mut mov_list = Vec::new();
+
+        if !mov_generator.is_in_check(state) {
+            mov_list.extend(mov_generator.gen_castle_mov_list(state).iter());
+        }
+
+        for mov in cap_list.iter().chain(non_cap_list.iter()) {
+            let mov = *mov;
+            let (from, to, tp, promo) = util::decode_u32_mov(mov);
+            let undo_info = state.do_mov(from, to, tp, promo);
+            state.player = def::get_opposite_player(state.player);
+
+            if !mov_generator.is_in_check(state) {
+                mov_list.push(mov);
+            }
+
+            state.player = def::get_opposite_player(state.player);
+            state.undo_mov(from, to, tp, undo_info);
+        }
+
+        mov_list
+    }
+
+    fn gen_legal_movs_test_helper(fen: &str, expected_mov_count: usize) {
+        let mut state = State::new(fen);
+        let mov_generator = MoveGenerator::new();
+
+        let mut legal_mov_list = mov_generator.gen_legal_mov_list(&state).to_vec();
+        let mut expected_mov_list = gen_legal_movs_by_mov_making(&mut state, &mov_generator);
+
+        legal_mov_list.sort();
+        expected_mov_list.sort();
+
+        assert_eq!(expected_mov_list, legal_mov_list, "legal moves do not match for {}", fen);
+        assert_eq!(expected_mov_count, legal_mov_list.len());
+    }
+
+    #[test]
+    fn test_gen_legal_movs_1() {
+        gen_legal_movs_test_helper("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 20);
+        gen_legal_movs_test_helper("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 48);
+        gen_legal_movs_test_helper("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 14);
+        gen_legal_movs_test_helper("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", 6);
+        gen_legal_movs_test_helper("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", 44);
+    }
+
+    #[test]
+    fn test_gen_legal_movs_pins() {
+        gen_legal_movs_test_helper("4k3/4r3/8/8/8/8/4R3/4K3 w - - 0 1", 9);
+        gen_legal_movs_test_helper("4k3/8/8/b7/8/8/3N4/4K3 w - - 0 1", 4);
+        gen_legal_movs_test_helper("4k3/8/8/8/8/2b5/3B4/4K3 w - - 0 1", 5);
+    }
+
+    #[test]
+    fn test_gen_legal_movs_checks() {
+        gen_legal_movs_test_helper("4k3/8/8/8/8/5n2/8/R3K2R w KQ - 0 1", 4);
+        gen_legal_movs_test_helper("4k3/8/8/8/1b6/8/8/R3K2R w KQ - 0 1", 4);
+        gen_legal_movs_test_helper("4k3/8/8/8/1b6/8/4r3/R3K2R w KQ - 0 1", 3);
+        gen_legal_movs_test_helper("3R2k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", 0);
+        gen_legal_movs_test_helper("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 0);
+    }
+
+    #[test]
+    fn test_gen_legal_movs_enp() {
+        gen_legal_movs_test_helper("8/8/8/8/k2Pp2Q/8/8/3K4 b - d3 0 1", 6);
+        gen_legal_movs_test_helper("8/8/3p4/1Pp4r/1K5k/8/8/8 w - c6 0 1", 7);
+        gen_legal_movs_test_helper("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1", 9);
+        gen_legal_movs_test_helper("8/8/8/1k6/3Pp3/8/8/4KQ2 b - d3 0 1", 6);
+    }
 }
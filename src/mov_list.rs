@@ -0,0 +1,155 @@
+use std::fmt;
+use std::ops::Deref;
+
+pub const MAX_MOV_COUNT: usize = 256;
+
+#[derive(Clone, Copy)]
+pub struct MoveList {
+    mov_list: [u32; MAX_MOV_COUNT],
+    score_list: [i64; MAX_MOV_COUNT],
+    mov_count: usize,
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MoveList {
+    pub fn new() -> MoveList {
+        MoveList {
+            mov_list: [0; MAX_MOV_COUNT],
+            score_list: [0; MAX_MOV_COUNT],
+            mov_count: 0,
+        }
+    }
+
+    #[inline]
+    pub fn push(&mut self, mov: u32) {
+        self.push_scored(mov, 0);
+    }
+
+    // positions accepted by state::validate_fen stay well below MAX_MOV_COUNT moves
+    #[inline]
+    pub fn push_scored(&mut self, mov: u32, score: i64) {
+        self.mov_list[self.mov_count] = mov;
+        self.score_list[self.mov_count] = score;
+        self.mov_count += 1;
+    }
+
+    pub fn extend_from_slice(&mut self, mov_list: &[u32]) {
+        for mov in mov_list {
+            self.push(*mov);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.mov_count = 0;
+    }
+
+    pub fn pick_best(&mut self) -> Option<u32> {
+        if self.mov_count == 0 {
+            return None
+        }
+
+        let mut best_index = 0;
+
+        for index in 1..self.mov_count {
+            if self.score_list[index] > self.score_list[best_index] {
+                best_index = index;
+            }
+        }
+
+        let mov = self.mov_list[best_index];
+
+        self.mov_list.copy_within(best_index + 1..self.mov_count, best_index);
+        self.score_list.copy_within(best_index + 1..self.mov_count, best_index);
+        self.mov_count -= 1;
+
+        Some(mov)
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [u32];
+
+    #[inline]
+    fn deref(&self) -> &[u32] {
+        &self.mov_list[..self.mov_count]
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a u32;
+    type IntoIter = std::slice::Iter<'a, u32>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl PartialEq for MoveList {
+    fn eq(&self, other: &MoveList) -> bool {
+        **self == **other
+    }
+}
+
+impl fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mov_list() {
+        let mut mov_list = MoveList::new();
+        assert!(mov_list.is_empty());
+        assert_eq!(None, mov_list.pick_best());
+
+        mov_list.push_scored(1, 10);
+        mov_list.push_scored(2, 30);
+        mov_list.push_scored(3, 30);
+        mov_list.push(4);
+        mov_list.extend_from_slice(&[5, 6]);
+
+        assert_eq!(6, mov_list.len());
+        assert_eq!(&[1, 2, 3, 4, 5, 6], &mov_list[..]);
+        assert!(mov_list.contains(&5));
+
+        assert_eq!(Some(2), mov_list.pick_best());
+        assert_eq!(Some(3), mov_list.pick_best());
+        assert_eq!(Some(1), mov_list.pick_best());
+        assert_eq!(&[4, 5, 6], &mov_list[..]);
+        assert_eq!(Some(4), mov_list.pick_best());
+
+        mov_list.clear();
+        assert!(mov_list.is_empty());
+    }
+
+    #[test]
+    fn test_mov_list_capacity() {
+        let mut mov_list = MoveList::new();
+
+        for mov in 0..MAX_MOV_COUNT as u32 {
+            mov_list.push(mov);
+        }
+
+        assert_eq!(MAX_MOV_COUNT, mov_list.len());
+        assert_eq!(Some(0), mov_list.pick_best());
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn test_mov_list_overflow() {
+        let mut mov_list = MoveList::new();
+
+        for mov in 0..=MAX_MOV_COUNT as u32 {
+            mov_list.push(mov);
+        }
+    }
+}
@@ -9,340 +9,121 @@ const HORIZONTAL_SLIDE_MOVS: [isize; 7] = [1, 2, 3, 4, 5, 6, 7];
 const DESC_DIAGNOL_SLIDE_MOVS: [isize; 7] = [15, 30, 45, 60, 75, 90, 105];
 const ASC_DIAGNOL_SLIDE_MOVS: [isize; 7] = [17, 34, 51, 68, 85, 102, 119];
 
-pub fn gen_n_mov_table() -> Vec<Vec<usize>> {
-    let mut mov_table = vec![vec![]; def::BOARD_SIZE];
+const MAX_MOV_COUNT: usize = 8;
 
-    let mut from_index = 0;
-
-    while from_index < def::BOARD_SIZE {
-        if !def::is_index_valid(from_index) {
-            from_index += 8;
-        }
-
-        let mut mov_list_on_index = Vec::new();
-
-        for mov_index in 0..8 {
-            let mov = N_MOVS[mov_index];
-            let to_index = from_index as isize + mov;
-            if to_index < 0 {
-                continue
-            }
-
-            let to_index = to_index as usize;
-
-            if def::is_index_valid(to_index) {
-                mov_list_on_index.push(to_index);
-            }
-        }
-
-        mov_table[from_index] = mov_list_on_index;
-        from_index += 1;
-    }
-
-    mov_table
+pub struct MovTable {
+    mov_index_list: [[usize; MAX_MOV_COUNT]; def::BOARD_SIZE],
+    mov_count_list: [usize; def::BOARD_SIZE],
 }
 
-pub fn gen_k_mov_table() -> Vec<Vec<usize>> {
-    let mut mov_table = vec![vec![]; def::BOARD_SIZE];
-
-    let mut from_index = 0;
-
-    while from_index < def::BOARD_SIZE {
-        if !def::is_index_valid(from_index) {
-            from_index += 8;
-        }
-
-        let mut mov_list_on_index = Vec::new();
-
-        for mov_index in 0..8 {
-            let mov = K_MOVS[mov_index];
-            let to_index = from_index as isize + mov;
-            if to_index < 0 {
-                continue
-            }
-
-            let to_index = to_index as usize;
-
-            if def::is_index_valid(to_index) {
-                mov_list_on_index.push(to_index);
-            }
-        }
-
-        mov_table[from_index] = mov_list_on_index;
-        from_index += 1;
+impl MovTable {
+    #[inline]
+    pub fn get(&self, from_index: usize) -> &[usize] {
+        &self.mov_index_list[from_index][..self.mov_count_list[from_index]]
     }
-
-    mov_table
 }
 
-pub fn gen_up_slide_mov_table() -> Vec<Vec<usize>> {
-    let mut mov_table = vec![vec![]; def::BOARD_SIZE];
+pub static N_MOV_TABLE: MovTable = gen_step_mov_table(&N_MOVS);
+pub static K_MOV_TABLE: MovTable = gen_step_mov_table(&K_MOVS);
 
-    let mut from_index = 0;
+pub static UP_MOV_TABLE: MovTable = gen_slide_mov_table(&VERTICAL_SLIDE_MOVS, 1);
+pub static DOWN_MOV_TABLE: MovTable = gen_slide_mov_table(&VERTICAL_SLIDE_MOVS, -1);
+pub static RIGHT_MOV_TABLE: MovTable = gen_slide_mov_table(&HORIZONTAL_SLIDE_MOVS, 1);
+pub static LEFT_MOV_TABLE: MovTable = gen_slide_mov_table(&HORIZONTAL_SLIDE_MOVS, -1);
 
-    while from_index < def::BOARD_SIZE {
-        if !def::is_index_valid(from_index) {
-            from_index += 8;
-        }
+pub static UP_LEFT_MOV_TABLE: MovTable = gen_slide_mov_table(&DESC_DIAGNOL_SLIDE_MOVS, 1);
+pub static DOWN_RIGHT_MOV_TABLE: MovTable = gen_slide_mov_table(&DESC_DIAGNOL_SLIDE_MOVS, -1);
+pub static UP_RIGHT_MOV_TABLE: MovTable = gen_slide_mov_table(&ASC_DIAGNOL_SLIDE_MOVS, 1);
+pub static DOWN_LEFT_MOV_TABLE: MovTable = gen_slide_mov_table(&ASC_DIAGNOL_SLIDE_MOVS, -1);
 
-        let mut mov_list_on_index = Vec::new();
-
-        for mov_index in 0..7 {
-            let mov = VERTICAL_SLIDE_MOVS[mov_index];
-            let to_index = from_index as isize + mov;
-
-            let to_index = to_index as usize;
-
-            if !def::is_index_valid(to_index) {
-                break
-            }
-
-            mov_list_on_index.push(to_index);
-        }
-
-        mov_table[from_index] = mov_list_on_index;
-        from_index += 1;
-    }
-
-    mov_table
-}
-
-pub fn gen_down_slide_mov_table() -> Vec<Vec<usize>> {
-    let mut mov_table = vec![vec![]; def::BOARD_SIZE];
+const fn gen_step_mov_table(mov_list: &[isize; 8]) -> MovTable {
+    let mut mov_table = MovTable {
+        mov_index_list: [[0; MAX_MOV_COUNT]; def::BOARD_SIZE],
+        mov_count_list: [0; def::BOARD_SIZE],
+    };
 
     let mut from_index = 0;
-
     while from_index < def::BOARD_SIZE {
-        if !def::is_index_valid(from_index) {
-            from_index += 8;
-        }
-
-        let mut mov_list_on_index = Vec::new();
-
-        for mov_index in 0..7 {
-            let mov = VERTICAL_SLIDE_MOVS[mov_index];
-            let to_index = from_index as isize - mov;
-
-            if to_index < 0 {
-                break
-            }
+        if def::is_index_valid(from_index) {
+            let mut mov_index = 0;
+            while mov_index < mov_list.len() {
+                let to_index = from_index as isize + mov_list[mov_index];
 
-            let to_index = to_index as usize;
+                if to_index >= 0 && def::is_index_valid(to_index as usize) {
+                    mov_table.mov_index_list[from_index][mov_table.mov_count_list[from_index]] = to_index as usize;
+                    mov_table.mov_count_list[from_index] += 1;
+                }
 
-            if !def::is_index_valid(to_index) {
-                break
+                mov_index += 1;
             }
-
-            mov_list_on_index.push(to_index);
         }
 
-        mov_table[from_index] = mov_list_on_index;
         from_index += 1;
     }
 
     mov_table
 }
 
-pub fn gen_right_slide_mov_table() -> Vec<Vec<usize>> {
-    let mut mov_table = vec![vec![]; def::BOARD_SIZE];
+const fn gen_slide_mov_table(mov_list: &[isize; 7], mov_sign: isize) -> MovTable {
+    let mut mov_table = MovTable {
+        mov_index_list: [[0; MAX_MOV_COUNT]; def::BOARD_SIZE],
+        mov_count_list: [0; def::BOARD_SIZE],
+    };
 
     let mut from_index = 0;
-
     while from_index < def::BOARD_SIZE {
-        if !def::is_index_valid(from_index) {
-            from_index += 8;
-        }
-
-        let mut mov_list_on_index = Vec::new();
+        if def::is_index_valid(from_index) {
+            let mut mov_index = 0;
+            while mov_index < mov_list.len() {
+                let to_index = from_index as isize + mov_list[mov_index] * mov_sign;
 
-        for mov_index in 0..7 {
-            let mov = HORIZONTAL_SLIDE_MOVS[mov_index];
-            let to_index = from_index as isize + mov;
+                if to_index < 0 || !def::is_index_valid(to_index as usize) {
+                    break
+                }
 
-            let to_index = to_index as usize;
-
-            if !def::is_index_valid(to_index) {
-                break
+                mov_table.mov_index_list[from_index][mov_table.mov_count_list[from_index]] = to_index as usize;
+                mov_table.mov_count_list[from_index] += 1;
+                mov_index += 1;
             }
-
-            mov_list_on_index.push(to_index);
         }
 
-        mov_table[from_index] = mov_list_on_index;
         from_index += 1;
     }
 
     mov_table
 }
 
-pub fn gen_left_slide_mov_table() -> Vec<Vec<usize>> {
-    let mut mov_table = vec![vec![]; def::BOARD_SIZE];
-
-    let mut from_index = 0;
-
-    while from_index < def::BOARD_SIZE {
-        if !def::is_index_valid(from_index) {
-            from_index += 8;
-        }
-
-        let mut mov_list_on_index = Vec::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util;
 
-        for mov_index in 0..7 {
-            let mov = HORIZONTAL_SLIDE_MOVS[mov_index];
-            let to_index = from_index as isize - mov;
-
-            if to_index < 0 {
-                break
-            }
-
-            let to_index = to_index as usize;
-
-            if !def::is_index_valid(to_index) {
-                break
-            }
-
-            mov_list_on_index.push(to_index);
-        }
-
-        mov_table[from_index] = mov_list_on_index;
-        from_index += 1;
+    fn map_notation_list(mov_table: &MovTable, sqr_notation: &str) -> Vec<String> {
+        mov_table.get(util::map_sqr_notation_to_index(sqr_notation)).iter()
+            .map(|index| util::map_index_to_sqr_notation(*index))
+            .collect()
     }
 
-    mov_table
-}
-
-pub fn gen_up_left_slide_mov_table() -> Vec<Vec<usize>> {
-    let mut mov_table = vec![vec![]; def::BOARD_SIZE];
-
-    let mut from_index = 0;
-
-    while from_index < def::BOARD_SIZE {
-        if !def::is_index_valid(from_index) {
-            from_index += 8;
-        }
-
-        let mut mov_list_on_index = Vec::new();
-
-        for mov_index in 0..7 {
-            let mov = DESC_DIAGNOL_SLIDE_MOVS[mov_index];
-            let to_index = from_index as isize + mov;
-
-            let to_index = to_index as usize;
-
-            if !def::is_index_valid(to_index) {
-                break
-            }
-
-            mov_list_on_index.push(to_index);
-        }
-
-        mov_table[from_index] = mov_list_on_index;
-        from_index += 1;
-    }
-
-    mov_table
-}
-
-pub fn gen_down_right_slide_mov_table() -> Vec<Vec<usize>> {
-    let mut mov_table = vec![vec![]; def::BOARD_SIZE];
-
-    let mut from_index = 0;
-
-    while from_index < def::BOARD_SIZE {
-        if !def::is_index_valid(from_index) {
-            from_index += 8;
-        }
-
-        let mut mov_list_on_index = Vec::new();
-
-        for mov_index in 0..7 {
-            let mov = DESC_DIAGNOL_SLIDE_MOVS[mov_index];
-            let to_index = from_index as isize - mov;
-
-            if to_index < 0 {
-                break
-            }
-
-            let to_index = to_index as usize;
-
-            if !def::is_index_valid(to_index) {
-                break
-            }
-
-            mov_list_on_index.push(to_index);
-        }
-
-        mov_table[from_index] = mov_list_on_index;
-        from_index += 1;
-    }
-
-    mov_table
-}
-
-pub fn gen_up_right_slide_mov_table() -> Vec<Vec<usize>> {
-    let mut mov_table = vec![vec![]; def::BOARD_SIZE];
-
-    let mut from_index = 0;
-
-    while from_index < def::BOARD_SIZE {
-        if !def::is_index_valid(from_index) {
-            from_index += 8;
-        }
-
-        let mut mov_list_on_index = Vec::new();
-
-        for mov_index in 0..7 {
-            let mov = ASC_DIAGNOL_SLIDE_MOVS[mov_index];
-            let to_index = from_index as isize + mov;
-
-            let to_index = to_index as usize;
-
-            if !def::is_index_valid(to_index) {
-                break
-            }
-
-            mov_list_on_index.push(to_index);
-        }
-
-        mov_table[from_index] = mov_list_on_index;
-        from_index += 1;
+    #[test]
+    fn test_step_mov_tables() {
+        assert_eq!(vec!["c2", "b3"], map_notation_list(&N_MOV_TABLE, "a1"));
+        assert_eq!(8, N_MOV_TABLE.get(util::map_sqr_notation_to_index("e4")).len());
+        assert_eq!(vec!["b1", "a2", "b2"], map_notation_list(&K_MOV_TABLE, "a1"));
+        assert_eq!(8, K_MOV_TABLE.get(util::map_sqr_notation_to_index("e4")).len());
+        assert!(N_MOV_TABLE.get(8).is_empty());
     }
 
-    mov_table
-}
-
-pub fn gen_down_left_slide_mov_table() -> Vec<Vec<usize>> {
-    let mut mov_table = vec![vec![]; def::BOARD_SIZE];
-
-    let mut from_index = 0;
-
-    while from_index < def::BOARD_SIZE {
-        if !def::is_index_valid(from_index) {
-            from_index += 8;
-        }
-
-        let mut mov_list_on_index = Vec::new();
-
-        for mov_index in 0..7 {
-            let mov = ASC_DIAGNOL_SLIDE_MOVS[mov_index];
-            let to_index = from_index as isize - mov;
-
-            if to_index < 0 {
-                break
-            }
-
-            let to_index = to_index as usize;
-
-            if !def::is_index_valid(to_index) {
-                break
-            }
-
-            mov_list_on_index.push(to_index);
-        }
-
-        mov_table[from_index] = mov_list_on_index;
-        from_index += 1;
+    #[test]
+    fn test_slide_mov_tables() {
+        assert_eq!(vec!["e5", "e6", "e7", "e8"], map_notation_list(&UP_MOV_TABLE, "e4"));
+        assert_eq!(vec!["e3", "e2", "e1"], map_notation_list(&DOWN_MOV_TABLE, "e4"));
+        assert_eq!(vec!["f4", "g4", "h4"], map_notation_list(&RIGHT_MOV_TABLE, "e4"));
+        assert_eq!(vec!["d4", "c4", "b4", "a4"], map_notation_list(&LEFT_MOV_TABLE, "e4"));
+        assert_eq!(vec!["d5", "c6", "b7", "a8"], map_notation_list(&UP_LEFT_MOV_TABLE, "e4"));
+        assert_eq!(vec!["f3", "g2", "h1"], map_notation_list(&DOWN_RIGHT_MOV_TABLE, "e4"));
+        assert_eq!(vec!["f5", "g6", "h7"], map_notation_list(&UP_RIGHT_MOV_TABLE, "e4"));
+        assert_eq!(vec!["d3", "c2", "b1"], map_notation_list(&DOWN_LEFT_MOV_TABLE, "e4"));
+        assert!(LEFT_MOV_TABLE.get(util::map_sqr_notation_to_index("a1")).is_empty());
+        assert_eq!(7, UP_RIGHT_MOV_TABLE.get(util::map_sqr_notation_to_index("a1")).len());
     }
-
-    mov_table
 }
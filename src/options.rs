@@ -0,0 +1,201 @@
+use std::fmt;
+
+pub const DEFAULT_HASH_SIZE_MB: usize = 64;
+pub const DEFAULT_MOVE_OVERHEAD: u128 = 50;
+pub const DEFAULT_CONTEMPT: i32 = 0;
+pub const DEFAULT_WINDOW_SIZE: i32 = 10;
+pub const DEFAULT_NULL_MOV_REDUCTION: u8 = 3;
+
+const MAX_HASH_SIZE_MB: i64 = 4096;
+const MAX_MULTI_PV: i64 = 256;
+
+const SEARCH_ALGORITHM_PVS: &str = "PVS";
+const SEARCH_ALGORITHM_ALPHA_BETA: &str = "AlphaBeta";
+
+pub enum OptionType {
+    Check { default: bool },
+    Spin { default: i64, min: i64, max: i64 },
+    Combo { default: &'static str, var_list: &'static [&'static str] },
+    Button,
+}
+
+pub struct OptionDecl {
+    pub name: &'static str,
+    pub tp: OptionType,
+}
+
+pub const OPTION_DECL_LIST: [OptionDecl; 10] = [
+    OptionDecl { name: "Hash", tp: OptionType::Spin { default: DEFAULT_HASH_SIZE_MB as i64, min: 1, max: MAX_HASH_SIZE_MB } },
+    OptionDecl { name: "Clear Hash", tp: OptionType::Button },
+    OptionDecl { name: "Threads", tp: OptionType::Spin { default: 1, min: 1, max: 1 } },
+    OptionDecl { name: "MultiPV", tp: OptionType::Spin { default: 1, min: 1, max: MAX_MULTI_PV } },
+    OptionDecl { name: "Ponder", tp: OptionType::Check { default: false } },
+    OptionDecl { name: "Move Overhead", tp: OptionType::Spin { default: DEFAULT_MOVE_OVERHEAD as i64, min: 0, max: 5000 } },
+    OptionDecl { name: "Contempt", tp: OptionType::Spin { default: DEFAULT_CONTEMPT as i64, min: -100, max: 100 } },
+    OptionDecl { name: "Aspiration Window", tp: OptionType::Spin { default: DEFAULT_WINDOW_SIZE as i64, min: 1, max: 500 } },
+    OptionDecl { name: "Null Move Reduction", tp: OptionType::Spin { default: DEFAULT_NULL_MOV_REDUCTION as i64, min: 1, max: 4 } },
+    OptionDecl { name: "Search Algorithm", tp: OptionType::Combo { default: SEARCH_ALGORITHM_ALPHA_BETA, var_list: &[SEARCH_ALGORITHM_PVS, SEARCH_ALGORITHM_ALPHA_BETA] } },
+];
+
+#[derive(Debug, PartialEq)]
+pub enum OptionValue {
+    Check(bool),
+    Spin(i64),
+    Combo(&'static str),
+    Button,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum EngineOption {
+    Hash(usize),
+    ClearHash,
+    Threads(usize),
+    MultiPv(usize),
+    Ponder(bool),
+    MoveOverhead(u128),
+    Contempt(i32),
+    AspirationWindow(i32),
+    NullMoveReduction(u8),
+    PvsEnabled(bool),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum OptionError {
+    UnknownOption(String),
+    MissingValue(&'static str),
+    UnexpectedValue(&'static str),
+    InvalidValue(&'static str, String),
+    OutOfRange(&'static str, i64, i64, i64),
+}
+
+impl fmt::Display for OptionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OptionError::UnknownOption(name) => write!(f, "unknown option {}", name),
+            OptionError::MissingValue(name) => write!(f, "missing value for option {}", name),
+            OptionError::UnexpectedValue(name) => write!(f, "option {} does not take a value", name),
+            OptionError::InvalidValue(name, value) => write!(f, "invalid value {} for option {}", value, name),
+            OptionError::OutOfRange(name, value, min, max) => write!(f, "value {} for option {} is out of range [{}, {}]", value, name, min, max),
+        }
+    }
+}
+
+impl OptionDecl {
+    pub fn parse_value(&self, value: Option<&str>) -> Result<OptionValue, OptionError> {
+        if let OptionType::Button = self.tp {
+            return match value {
+                Some(_) => Err(OptionError::UnexpectedValue(self.name)),
+                None => Ok(OptionValue::Button),
+            }
+        }
+
+        let value = value.ok_or(OptionError::MissingValue(self.name))?;
+
+        match self.tp {
+            OptionType::Check { .. } => match value {
+                "true" => Ok(OptionValue::Check(true)),
+                "false" => Ok(OptionValue::Check(false)),
+                _ => Err(OptionError::InvalidValue(self.name, value.to_owned())),
+            },
+            OptionType::Spin { min, max, .. } => {
+                let spin_value = value.parse::<i64>().map_err(|_| OptionError::InvalidValue(self.name, value.to_owned()))?;
+
+                if spin_value < min || spin_value > max {
+                    return Err(OptionError::OutOfRange(self.name, spin_value, min, max))
+                }
+
+                Ok(OptionValue::Spin(spin_value))
+            },
+            OptionType::Combo { var_list, .. } => {
+                match var_list.iter().find(|var| var.eq_ignore_ascii_case(value)) {
+                    Some(var) => Ok(OptionValue::Combo(var)),
+                    None => Err(OptionError::InvalidValue(self.name, value.to_owned())),
+                }
+            },
+            OptionType::Button => unreachable!(),
+        }
+    }
+}
+
+impl fmt::Display for OptionDecl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "option name {} type ", self.name)?;
+
+        match self.tp {
+            OptionType::Check { default } => write!(f, "check default {}", default),
+            OptionType::Spin { default, min, max } => write!(f, "spin default {} min {} max {}", default, min, max),
+            OptionType::Combo { default, var_list } => {
+                write!(f, "combo default {}", default)?;
+                for var in var_list {
+                    write!(f, " var {}", var)?;
+                }
+
+                Ok(())
+            },
+            OptionType::Button => write!(f, "button"),
+        }
+    }
+}
+
+pub fn parse_engine_option(name: &str, value: Option<&str>) -> Result<EngineOption, OptionError> {
+    let option_decl = OPTION_DECL_LIST.iter()
+        .find(|option_decl| option_decl.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| OptionError::UnknownOption(name.to_owned()))?;
+
+    let engine_option = match (option_decl.name, option_decl.parse_value(value)?) {
+        ("Hash", OptionValue::Spin(size_mb)) => EngineOption::Hash(size_mb as usize),
+        ("Clear Hash", OptionValue::Button) => EngineOption::ClearHash,
+        ("Threads", OptionValue::Spin(thread_count)) => EngineOption::Threads(thread_count as usize),
+        ("MultiPV", OptionValue::Spin(multi_pv)) => EngineOption::MultiPv(multi_pv as usize),
+        ("Ponder", OptionValue::Check(ponder)) => EngineOption::Ponder(ponder),
+        ("Move Overhead", OptionValue::Spin(overhead_time)) => EngineOption::MoveOverhead(overhead_time as u128),
+        ("Contempt", OptionValue::Spin(contempt)) => EngineOption::Contempt(contempt as i32),
+        ("Aspiration Window", OptionValue::Spin(window_size)) => EngineOption::AspirationWindow(window_size as i32),
+        ("Null Move Reduction", OptionValue::Spin(reduction)) => EngineOption::NullMoveReduction(reduction as u8),
+        ("Search Algorithm", OptionValue::Combo(algorithm)) => EngineOption::PvsEnabled(algorithm == SEARCH_ALGORITHM_PVS),
+        (name, _) => unreachable!("option {} is declared without a handler", name),
+    };
+
+    Ok(engine_option)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_option_decl_display() {
+        assert_eq!("option name Hash type spin default 64 min 1 max 4096", OPTION_DECL_LIST[0].to_string());
+        assert_eq!("option name Clear Hash type button", OPTION_DECL_LIST[1].to_string());
+        assert_eq!("option name Ponder type check default false", OPTION_DECL_LIST[4].to_string());
+        assert_eq!("option name Search Algorithm type combo default AlphaBeta var PVS var AlphaBeta", OPTION_DECL_LIST[9].to_string());
+    }
+
+    #[test]
+    fn test_parse_value() {
+        let check_decl = &OPTION_DECL_LIST[4];
+        assert_eq!(Ok(OptionValue::Check(true)), check_decl.parse_value(Some("true")));
+        assert_eq!(Err(OptionError::InvalidValue("Ponder", "yes".to_owned())), check_decl.parse_value(Some("yes")));
+        assert_eq!(Err(OptionError::MissingValue("Ponder")), check_decl.parse_value(None));
+
+        let button_decl = &OPTION_DECL_LIST[1];
+        assert_eq!(Ok(OptionValue::Button), button_decl.parse_value(None));
+        assert_eq!(Err(OptionError::UnexpectedValue("Clear Hash")), button_decl.parse_value(Some("1")));
+    }
+
+    #[test]
+    fn test_parse_engine_option() {
+        assert_eq!(Ok(EngineOption::Hash(128)), parse_engine_option("Hash", Some("128")));
+        assert_eq!(Ok(EngineOption::MoveOverhead(100)), parse_engine_option("move overhead", Some("100")));
+        assert_eq!(Ok(EngineOption::Contempt(-20)), parse_engine_option("Contempt", Some("-20")));
+        assert_eq!(Ok(EngineOption::PvsEnabled(false)), parse_engine_option("Search Algorithm", Some("alphabeta")));
+        assert_eq!(Ok(EngineOption::ClearHash), parse_engine_option("Clear Hash", None));
+
+        assert_eq!(Err(OptionError::OutOfRange("Hash", 0, 1, 4096)), parse_engine_option("Hash", Some("0")));
+        assert_eq!(Err(OptionError::InvalidValue("MultiPV", "two".to_owned())), parse_engine_option("MultiPV", Some("two")));
+        assert_eq!(Ok(EngineOption::Threads(1)), parse_engine_option("Threads", Some("1")));
+        assert_eq!(Err(OptionError::OutOfRange("Threads", 2, 1, 1)), parse_engine_option("Threads", Some("2")));
+        assert_eq!(Err(OptionError::InvalidValue("Search Algorithm", "MTD".to_owned())), parse_engine_option("Search Algorithm", Some("MTD")));
+        assert_eq!(Err(OptionError::UnknownOption("Style".to_owned())), parse_engine_option("Style", Some("Aggressive")));
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{
+    mov_gen::MoveGenerator,
+    state::State,
+    util,
+};
+
+pub fn perft(state: &mut State, mov_generator: &MoveGenerator, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1
+    }
+
+    let mov_list = mov_generator.gen_legal_mov_list(state);
+
+    if depth == 1 {
+        return mov_list.len() as u64
+    }
+
+    let mut node_count = 0;
+
+    for mov in &mov_list {
+        let (from, to, tp, promo) = util::decode_u32_mov(*mov);
+        let undo_info = state.do_mov(from, to, tp, promo);
+        node_count += perft(state, mov_generator, depth - 1);
+        state.undo_mov(from, to, tp, undo_info);
+    }
+
+    node_count
+}
+
+pub fn divide(state: &mut State, mov_generator: &MoveGenerator, depth: u8) -> Vec<(u32, u64)> {
+    let mut divide_list = Vec::new();
+
+    if depth == 0 {
+        return divide_list
+    }
+
+    for mov in &mov_generator.gen_legal_mov_list(state) {
+        let mov = *mov;
+        let (from, to, tp, promo) = util::decode_u32_mov(mov);
+        let undo_info = state.do_mov(from, to, tp, promo);
+        divide_list.push((mov, perft(state, mov_generator, depth - 1)));
+        state.undo_mov(from, to, tp, undo_info);
+    }
+
+    divide_list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn perft_test_helper(fen: &str, expected_node_count_list: &[u64]) {
+        let mut state = State::new(fen);
+        let mov_generator = MoveGenerator::new();
+
+        for (depth, expected_node_count) in expected_node_count_list.iter().enumerate() {
+            assert_eq!(*expected_node_count, perft(&mut state, &mov_generator, depth as u8 + 1), "perft {} failed for {}", depth + 1, fen);
+        }
+
+        assert_eq!(State::new(fen).hash_key, state.hash_key);
+    }
+
+    #[test]
+    fn test_perft_startpos() {
+        perft_test_helper("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &[20, 400, 8902, 197281, 4865609]);
+    }
+
+    #[test]
+    fn test_perft_kiwipete() {
+        perft_test_helper("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[48, 2039, 97862, 4085603]);
+    }
+
+    #[test]
+    fn test_perft_enp() {
+        perft_test_helper("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2812, 43238, 674624]);
+    }
+
+    #[test]
+    fn test_perft_promo() {
+        perft_test_helper("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", &[6, 264, 9467, 422333]);
+        perft_test_helper("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", &[44, 1486, 62379, 2103487]);
+    }
+
+    #[test]
+    fn test_perft_middlegame() {
+        perft_test_helper("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", &[46, 2079, 89890, 3894594]);
+    }
+
+    #[test]
+    fn test_divide() {
+        let mut state = State::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let mov_generator = MoveGenerator::new();
+
+        let divide_list = divide(&mut state, &mov_generator, 3);
+        assert_eq!(20, divide_list.len());
+        assert_eq!(8902, divide_list.iter().map(|(_mov, node_count)| node_count).sum::<u64>());
+
+        let (_mov, node_count) = divide_list.iter().find(|(mov, _node_count)| util::format_mov(*mov) == "e2e4").unwrap();
+        assert_eq!(600, *node_count);
+    }
+}
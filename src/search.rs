@@ -1,60 +1,358 @@
 use crate::{
     def,
     eval,
-    mov_gen::MoveGenerator,
+    hashtable::{HashTable, HASH_TYPE_EXACT, HASH_TYPE_LOWER, HASH_TYPE_UPPER},
+    mov_gen::{AttackerList, MoveGenerator},
+    mov_list::{MoveList, MAX_MOV_COUNT},
+    options::{self, EngineOption},
     state::State,
     util,
 };
 
-use std::u64;
-
 const NULL_MOV_MIN_DEPTH: u8 = 5;
-const NULL_MOV_REDUCTION: u8 = 3;
 const PV_TRACK_LENGTH: usize = 12;
-const REFUTATION_TABLE_SIZE: usize = 128;
-const MAX_HISTORY_SCORE: u64 = u64::MAX;
+const MAX_PLY_COUNT: usize = u8::MAX as usize + 1;
+const KILLER_SLOT_COUNT: usize = 2;
+const COUNTER_MOV_TABLE_SIZE: usize = def::WK as usize + 1;
+const MAX_HISTORY_SCORE: i64 = i64::MAX;
+const HISTORY_AGE_SHIFT: u32 = 1;
 
-const WINDOW_SIZE: i32 = 10;
 const MIN_BRANCHING_FACTOR: u64 = 2;
+const MAX_DEPTH: u8 = 64;
+const DEFAULT_MOVS_TO_GO: u128 = 20;
+
+#[derive(Default)]
+pub struct SearchLimits {
+    pub wtime: Option<u128>,
+    pub btime: Option<u128>,
+    pub winc: Option<u128>,
+    pub binc: Option<u128>,
+    pub movestogo: Option<u128>,
+    pub movetime: Option<u128>,
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    pub mate: Option<u8>,
+    pub infinite: bool,
+    pub ponder: bool,
+    pub searchmoves: Vec<u32>,
+}
+
+impl SearchLimits {
+    pub fn get_max_time_millis(&self, player: u8, overhead_time: u128) -> u128 {
+        if self.infinite {
+            return u128::MAX
+        }
+
+        if let Some(movetime) = self.movetime {
+            return movetime.saturating_sub(overhead_time)
+        }
+
+        let (time_millis, inc_millis) = if player == def::PLAYER_W {
+            (self.wtime, self.winc)
+        } else {
+            (self.btime, self.binc)
+        };
+
+        match time_millis {
+            Some(time_millis) => {
+                let movs_to_go = self.movestogo.unwrap_or(DEFAULT_MOVS_TO_GO).max(1);
+                (time_millis / movs_to_go + inc_millis.unwrap_or(0)).min(time_millis).saturating_sub(overhead_time)
+            },
+            None => u128::MAX,
+        }
+    }
+
+    pub fn get_max_depth(&self) -> u8 {
+        let max_depth = self.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+
+        match self.mate {
+            Some(mate) => max_depth.min(mate.saturating_mul(2).max(1)),
+            None => max_depth,
+        }
+    }
+}
+
+pub struct PvLine {
+    pub mov: u32,
+    pub score: i32,
+    pub pv: Vec<u32>,
+}
+
+impl PvLine {
+    fn new(pv_table: &[u32], score: i32) -> PvLine {
+        PvLine {
+            mov: pv_table[0],
+            score,
+            pv: pv_table.iter().take_while(|mov| **mov != 0).copied().collect(),
+        }
+    }
+}
+
+pub struct SearchResult {
+    pub best_mov: u32,
+    pub score: i32,
+    pub pv: Vec<u32>,
+    pub ponder_mov: u32,
+    pub pv_line_list: Vec<PvLine>,
+    pub depth: u8,
+    pub node_count: u64,
+}
 
-pub enum SearchMovResult {
+enum SearchMovResult {
     Beta(i32),
     Alpha(i32),
     Noop,
 }
 
 use SearchMovResult::*;
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
 use std::time::Instant;
 
+#[derive(PartialEq)]
+enum MovePickerStage {
+    Hash,
+    GenCap,
+    Cap,
+    Killer,
+    GenNonCap,
+    NonCap,
+    Done,
+}
+
+struct MovePicker<'a> {
+    stage: MovePickerStage,
+    hash_mov: u32,
+    killer_mov_list: &'a [u32],
+    killer_index: usize,
+    scored_mov_list: MoveList,
+    depth: u8,
+    player_sign: i32,
+}
+
+impl<'a> MovePicker<'a> {
+    fn new(hash_mov: u32, killer_mov_list: &'a [u32], depth: u8, player_sign: i32) -> MovePicker<'a> {
+        MovePicker {
+            stage: MovePickerStage::Hash,
+            hash_mov,
+            killer_mov_list,
+            killer_index: 0,
+            scored_mov_list: MoveList::new(),
+            depth,
+            player_sign,
+        }
+    }
+
+    fn next(&mut self, search_engine: &SearchEngine, state: &State) -> Option<(u32, bool)> {
+        loop {
+            match self.stage {
+                MovePickerStage::Hash => {
+                    self.stage = MovePickerStage::GenCap;
+
+                    if self.hash_mov != 0 {
+                        let (_from, to, tp, _promo) = util::decode_u32_mov(self.hash_mov);
+                        return Some((self.hash_mov, state.squares[to] != 0 || tp == def::MOV_ENP))
+                    }
+                },
+                MovePickerStage::GenCap => {
+                    self.stage = MovePickerStage::Cap;
+
+                    let (cap_list, _) = search_engine.mov_generator.gen_reg_mov_list(state, true);
+                    let squares = state.squares;
+
+                    for cap in &cap_list {
+                        let cap = *cap;
+
+                        if cap == self.hash_mov {
+                            continue
+                        }
+
+                        let (from, to, _tp, promo) = util::decode_u32_mov(cap);
+
+                        let exchange_score = eval::val_of(squares[to]) - eval::val_of(squares[from]) + eval::val_of(promo);
+
+                        if exchange_score > eval::EQUAL_EXCHANGE_VAL || self.depth == 1 {
+                            self.scored_mov_list.push_scored(cap, exchange_score as i64);
+                        } else {
+                            let see_score = search_engine.see(state, to, squares[from]) * self.player_sign + eval::val_of(promo);
+                            self.scored_mov_list.push_scored(cap, see_score as i64);
+                        }
+                    }
+                },
+                MovePickerStage::Cap => {
+                    if let Some(cap) = self.scored_mov_list.pick_best() {
+                        return Some((cap, true))
+                    }
+
+                    self.stage = MovePickerStage::Killer;
+                },
+                MovePickerStage::Killer => {
+                    while self.killer_index < self.killer_mov_list.len() {
+                        let killer_mov = self.killer_mov_list[self.killer_index];
+                        self.killer_index += 1;
+
+                        let (_from, to, tp, _promo) = util::decode_u32_mov(killer_mov);
+
+                        if killer_mov != self.hash_mov && state.squares[to] == 0 && tp != def::MOV_ENP && search_engine.mov_generator.is_mov_valid(state, killer_mov) {
+                            return Some((killer_mov, false))
+                        }
+                    }
+
+                    self.stage = MovePickerStage::GenNonCap;
+                },
+                MovePickerStage::GenNonCap => {
+                    self.stage = MovePickerStage::NonCap;
+
+                    let mut non_cap_list = search_engine.mov_generator.gen_non_cap_mov_list(state);
+                    non_cap_list.extend_from_slice(&search_engine.mov_generator.gen_castle_mov_list(state));
+
+                    for non_cap in &non_cap_list {
+                        let non_cap = *non_cap;
+
+                        if non_cap == self.hash_mov || self.killer_mov_list.contains(&non_cap) {
+                            continue
+                        }
+
+                        let (from, to, _tp, promo) = util::decode_u32_mov(non_cap);
+                        if promo != 0 {
+                            self.scored_mov_list.push_scored(non_cap, MAX_HISTORY_SCORE);
+                            continue
+                        }
+
+                        self.scored_mov_list.push_scored(non_cap, search_engine.history_table[from][to] as i64);
+                    }
+                },
+                MovePickerStage::NonCap => {
+                    if let Some(non_cap) = self.scored_mov_list.pick_best() {
+                        return Some((non_cap, false))
+                    }
+
+                    self.stage = MovePickerStage::Done;
+                },
+                MovePickerStage::Done => return None,
+            }
+        }
+    }
+}
+
 pub struct SearchEngine {
     mov_generator: MoveGenerator,
+    hash_table: HashTable,
     history_table: [[u64; def::BOARD_SIZE]; def::BOARD_SIZE],
-    refutation_table: [(i32, u32); REFUTATION_TABLE_SIZE],
+    killer_table: [[u32; KILLER_SLOT_COUNT]; MAX_PLY_COUNT],
+    counter_mov_table: [[u32; def::BOARD_SIZE]; COUNTER_MOV_TABLE_SIZE],
     time_tracker: Instant,
 
+    pvs_enabled: bool,
     abort: bool,
+    pondering: bool,
+    stop_signal: Arc<AtomicBool>,
+    ponder_signal: Arc<AtomicBool>,
     max_time_millis: u128,
+    ponderhit_time_millis: u128,
+    max_node_count: u64,
+    overhead_time: u128,
+    contempt: i32,
+    draw_score: i32,
+    window_size: i32,
+    null_mov_reduction: u8,
+    multi_pv: usize,
+    root_mov_list: Vec<u32>,
+    excluded_root_mov_list: Vec<u32>,
+}
+
+impl Default for SearchEngine {
+    fn default() -> Self {
+        let mut search_engine = Self::new(options::DEFAULT_HASH_SIZE_MB);
+        search_engine.set_overhead_time(options::DEFAULT_MOVE_OVERHEAD);
+        search_engine
+    }
 }
 
 impl SearchEngine {
-    pub fn new() -> SearchEngine {
+    pub fn new(hash_size: usize) -> SearchEngine {
         SearchEngine {
             mov_generator: MoveGenerator::new(),
+            hash_table: HashTable::new(hash_size),
             history_table: [[0; def::BOARD_SIZE]; def::BOARD_SIZE],
-            refutation_table: [(0, 0); REFUTATION_TABLE_SIZE],
+            killer_table: [[0; KILLER_SLOT_COUNT]; MAX_PLY_COUNT],
+            counter_mov_table: [[0; def::BOARD_SIZE]; COUNTER_MOV_TABLE_SIZE],
             time_tracker: Instant::now(),
 
+            pvs_enabled: false,
             abort: false,
+            pondering: false,
+            stop_signal: Arc::new(AtomicBool::new(false)),
+            ponder_signal: Arc::new(AtomicBool::new(false)),
             max_time_millis: 0,
+            ponderhit_time_millis: 0,
+            max_node_count: 0,
+            overhead_time: 0,
+            contempt: options::DEFAULT_CONTEMPT,
+            draw_score: 0,
+            window_size: options::DEFAULT_WINDOW_SIZE,
+            null_mov_reduction: options::DEFAULT_NULL_MOV_REDUCTION,
+            multi_pv: 1,
+            root_mov_list: Vec::with_capacity(MAX_MOV_COUNT),
+            excluded_root_mov_list: Vec::new(),
+        }
+    }
+
+    pub fn get_stop_signal(&self) -> Arc<AtomicBool> {
+        self.stop_signal.clone()
+    }
+
+    pub fn get_ponder_signal(&self) -> Arc<AtomicBool> {
+        self.ponder_signal.clone()
+    }
+
+    pub fn set_overhead_time(&mut self, overhead_time: u128) {
+        self.overhead_time = overhead_time;
+    }
+
+    pub fn set_option(&mut self, engine_option: EngineOption) {
+        match engine_option {
+            EngineOption::Hash(size_mb) => self.hash_table = HashTable::new(size_mb),
+            EngineOption::ClearHash => self.reset(),
+            EngineOption::Threads(_) | EngineOption::Ponder(_) => {},
+            EngineOption::MultiPv(multi_pv) => self.multi_pv = multi_pv,
+            EngineOption::MoveOverhead(overhead_time) => self.overhead_time = overhead_time,
+            EngineOption::Contempt(contempt) => self.contempt = contempt,
+            EngineOption::AspirationWindow(window_size) => self.window_size = window_size,
+            EngineOption::NullMoveReduction(null_mov_reduction) => self.null_mov_reduction = null_mov_reduction,
+            EngineOption::PvsEnabled(pvs_enabled) => self.pvs_enabled = pvs_enabled,
         }
     }
 
-    pub fn search(&mut self, state: &mut State, max_time_millis: u128) -> u32 {
+    pub fn reset(&mut self) {
+        self.hash_table.clear();
+        self.history_table = [[0; def::BOARD_SIZE]; def::BOARD_SIZE];
+        self.counter_mov_table = [[0; def::BOARD_SIZE]; COUNTER_MOV_TABLE_SIZE];
+    }
+
+    pub fn search(&mut self, state: &mut State, search_limits: &SearchLimits) -> SearchResult {
         self.time_tracker = Instant::now();
-        self.max_time_millis = max_time_millis;
         self.abort = false;
-        self.history_table = [[0; def::BOARD_SIZE]; def::BOARD_SIZE];
-        self.refutation_table = [(0, 0); REFUTATION_TABLE_SIZE];
+        self.root_mov_list.clear();
+        self.root_mov_list.extend_from_slice(&search_limits.searchmoves);
+        self.excluded_root_mov_list.clear();
+        self.excluded_root_mov_list.reserve(self.multi_pv);
+        state.hash_key_stack.reserve(MAX_PLY_COUNT);
+
+        self.pondering = search_limits.ponder;
+        self.ponderhit_time_millis = search_limits.get_max_time_millis(state.player, self.overhead_time);
+        self.max_time_millis = if self.pondering {
+            u128::MAX
+        } else {
+            self.ponderhit_time_millis
+        };
+
+        let max_node_count = search_limits.nodes.unwrap_or(u64::MAX);
+        let max_depth = search_limits.get_max_depth();
+        self.age_history_table();
+        self.killer_table = [[0; KILLER_SLOT_COUNT]; MAX_PLY_COUNT];
 
         let player_sign = if state.player == def::PLAYER_W {
             1
@@ -62,81 +360,213 @@ impl SearchEngine {
             -1
         };
 
+        let draw_score = -self.contempt * player_sign;
+        if draw_score != self.draw_score {
+            self.hash_table.clear();
+            self.draw_score = draw_score;
+        }
+
+        let legal_mov_list = self.mov_generator.gen_legal_mov_list(state);
+
         let mut beta = player_sign * eval::K_VAL;
         let mut alpha = -beta;
 
         let mut depth = 1;
-        let mut best_mov = 0;
+        let mut search_result = SearchResult {
+            best_mov: 0,
+            score: 0,
+            pv: Vec::new(),
+            ponder_mov: 0,
+            pv_line_list: Vec::new(),
+            depth: 0,
+            node_count: 0,
+        };
+
+        if legal_mov_list.is_empty() {
+            search_result.score = if self.mov_generator.is_in_check(state) {
+                -eval::K_VAL
+            } else {
+                self.draw_score * player_sign
+            };
+
+            return search_result
+        }
+
+        let mut total_node_count = 0;
+        let mut depth_node_count = 0;
         let mut previous_node_count = 1;
         let mut time_after_previous_iter = self.time_tracker.elapsed().as_millis();
 
         loop {
+            self.check_ponderhit();
+
             let mut node_count = 0;
             let mut seldepth = 0;
 
             let mut pv_table = [0; PV_TRACK_LENGTH];
-            let score = self.ab_search(state, &mut pv_table, alpha, beta, depth, false, 0, 0, &mut node_count, &mut seldepth);
+            self.max_node_count = max_node_count.saturating_sub(total_node_count);
+            let score = self.ab_search(state, &mut pv_table, alpha, beta, depth, false, 0, 0, 0, &mut node_count, &mut seldepth);
+            total_node_count += node_count;
+            depth_node_count += node_count;
+            search_result.node_count = total_node_count;
 
             if self.abort {
+                if search_result.best_mov == 0 {
+                    let best_mov = if pv_table[0] != 0 {
+                        pv_table[0]
+                    } else {
+                        legal_mov_list.iter().copied().find(|mov| !self.is_root_mov_skipped(*mov)).unwrap_or(0)
+                    };
+
+                    search_result.best_mov = best_mov;
+                    search_result.pv = vec![best_mov];
+                }
+
                 break
             }
 
-            if score * player_sign <= alpha * player_sign {
+            if score * player_sign <= alpha * player_sign && alpha != -eval::K_VAL * player_sign {
                 alpha = -eval::K_VAL * player_sign;
                 continue
             }
 
-            if score * player_sign >= beta * player_sign {
+            if score * player_sign >= beta * player_sign && beta != eval::K_VAL * player_sign {
                 beta = eval::K_VAL * player_sign;
                 continue
             }
-    
-            best_mov = pv_table[0];
+
+            let mut pv_line_list = Vec::with_capacity(self.multi_pv);
+            pv_line_list.push(PvLine::new(&pv_table, score * player_sign));
+
+            while pv_line_list.len() < self.multi_pv {
+                self.excluded_root_mov_list.push(pv_line_list[pv_line_list.len() - 1].mov);
+
+                let mut node_count = 0;
+                let mut pv_table = [0; PV_TRACK_LENGTH];
+                self.max_node_count = max_node_count.saturating_sub(total_node_count);
+                let score = self.ab_search(state, &mut pv_table, -eval::K_VAL * player_sign, eval::K_VAL * player_sign, depth, false, 0, 0, 0, &mut node_count, &mut seldepth);
+                total_node_count += node_count;
+                depth_node_count += node_count;
+                search_result.node_count = total_node_count;
+
+                if self.abort || !legal_mov_list.contains(&pv_table[0]) {
+                    break
+                }
+
+                pv_line_list.push(PvLine::new(&pv_table, score * player_sign));
+            }
+
+            self.excluded_root_mov_list.clear();
+
+            if self.abort && search_result.best_mov != 0 {
+                break
+            }
+
+            pv_line_list.sort_by_key(|pv_line| -pv_line.score);
+
             let time_taken_millis = self.time_tracker.elapsed().as_millis();
-            let nps = (time_taken_millis / 1000).max(1) / node_count as u128;
+            let nps = total_node_count as u128 * 1000 / time_taken_millis.max(1);
+
+            for (pv_index, pv_line) in pv_line_list.iter().enumerate() {
+                let score_str = if pv_line.score.abs() > eval::TERM_VAL {
+                    format!("mate {}", (eval::K_VAL - pv_line.score.abs() + 1) / 2 * pv_line.score.signum())
+                } else {
+                    format!("cp {}", pv_line.score)
+                };
+
+                println!("info multipv {} score {} depth {} seldepth {} nodes {} nps {} time {} pv {}",
+                    pv_index + 1, score_str, depth, seldepth, total_node_count, nps, time_taken_millis, util::format_pv(&pv_line.pv));
+            }
 
-            if score * player_sign > eval::TERM_VAL {
-                println!("info score mate {} depth {} seldepth {} nodes {} nps {} time {} pv {}",
-                    (eval::K_VAL - score.abs() + 1) / 2, depth, seldepth, node_count, nps, time_taken_millis, util::format_pv(&pv_table));
+            search_result.best_mov = pv_line_list[0].mov;
+            search_result.score = pv_line_list[0].score;
+            search_result.pv = pv_line_list[0].pv.clone();
+            search_result.pv_line_list = pv_line_list;
+            search_result.depth = depth;
+
+            if search_result.score > eval::TERM_VAL {
                 break
             }
 
-            println!("info score cp {} depth {} seldepth {} nodes {} nps {} time {} pv {}",
-                score * player_sign, depth, seldepth, node_count, nps, time_taken_millis, util::format_pv(&pv_table));
+            if self.abort || depth >= max_depth || total_node_count >= max_node_count || self.stop_signal.load(Ordering::Relaxed) {
+                break
+            }
 
             depth += 1;
 
-            alpha = score - player_sign * WINDOW_SIZE;
-            beta = score + player_sign * WINDOW_SIZE;
+            alpha = score - player_sign * self.window_size;
+            beta = score + player_sign * self.window_size;
 
             let current_time_millis = self.time_tracker.elapsed().as_millis();
-            let estimated_time_for_next_iter = (node_count / previous_node_count).max(MIN_BRANCHING_FACTOR) as u128 * (current_time_millis - time_after_previous_iter);
+            let estimated_time_for_next_iter = (depth_node_count / previous_node_count).max(MIN_BRANCHING_FACTOR) as u128 * (current_time_millis - time_after_previous_iter);
 
-            if current_time_millis + estimated_time_for_next_iter > max_time_millis {
+            if current_time_millis + estimated_time_for_next_iter > self.max_time_millis {
                 break
             }
 
-            previous_node_count = node_count;
+            previous_node_count = depth_node_count.max(1);
+            depth_node_count = 0;
             time_after_previous_iter = current_time_millis;
         }
 
-        best_mov
+        search_result.ponder_mov = self.find_ponder_mov(state, &search_result.pv);
+
+        search_result
+    }
+
+    fn check_ponderhit(&mut self) {
+        if self.pondering && !self.ponder_signal.load(Ordering::Relaxed) {
+            self.pondering = false;
+            self.max_time_millis = self.time_tracker.elapsed().as_millis().saturating_add(self.ponderhit_time_millis);
+        }
+    }
+
+    fn find_ponder_mov(&self, state: &mut State, pv: &[u32]) -> u32 {
+        if pv.is_empty() {
+            return 0
+        }
+
+        if pv.len() > 1 {
+            return pv[1]
+        }
+
+        let (from, to, tp, promo) = util::decode_u32_mov(pv[0]);
+        let undo_info = state.do_mov(from, to, tp, promo);
+
+        let legal_mov_list = self.mov_generator.gen_legal_mov_list(state);
+        let ponder_mov = match self.hash_table.get(state.hash_key, 0) {
+            Some((_depth, _flag, _score, hash_mov)) if legal_mov_list.contains(&hash_mov) => hash_mov,
+            _ => legal_mov_list.first().copied().unwrap_or(0),
+        };
+
+        state.undo_mov(from, to, tp, undo_info);
+
+        ponder_mov
+    }
+
+    fn is_root_mov_skipped(&self, mov: u32) -> bool {
+        (!self.root_mov_list.is_empty() && !self.root_mov_list.contains(&mov)) || self.excluded_root_mov_list.contains(&mov)
     }
 
-    pub fn ab_search(&mut self, state: &mut State, pv_table: &mut [u32], mut alpha: i32, beta: i32, mut depth: u8, depth_reduced: bool, mut depth_extend_count: u8, ply: u8, node_count: &mut u64, seldepth: &mut u8) -> i32 {
+    #[allow(clippy::too_many_arguments)]
+    fn ab_search(&mut self, state: &mut State, pv_table: &mut [u32], mut alpha: i32, beta: i32, mut depth: u8, depth_reduced: bool, mut depth_extend_count: u8, ply: u8, last_mov: u32, node_count: &mut u64, seldepth: &mut u8) -> i32 {
         if self.abort {
             return 0
         }
 
-        if self.time_tracker.elapsed().as_millis() > self.max_time_millis {
+        if self.pondering {
+            self.check_ponderhit();
+        }
+
+        if self.stop_signal.load(Ordering::Relaxed) || *node_count >= self.max_node_count || self.time_tracker.elapsed().as_millis() > self.max_time_millis {
             self.abort = true;
             return 0
         }
 
         *node_count += 1;
 
-        if ply > 0 && state.is_draw() {
-            return 0
+        if ply > 0 && (state.is_draw(ply) || eval::is_material_draw(state)) {
+            return self.draw_score
         }
 
         let player_sign = if state.player == def::PLAYER_W {
@@ -156,105 +586,72 @@ impl SearchEngine {
             return self.q_search(state, alpha, beta, ply, seldepth)
         }
 
-        if !in_check && (!depth_reduced || depth_extend_count > 1) && depth >= NULL_MOV_MIN_DEPTH {
-            let mut next_pv_table = [0; PV_TRACK_LENGTH];
+        let mut hash_mov = 0;
 
-            state.do_null_mov();
-            let score = self.ab_search(state, &mut next_pv_table, beta, beta - player_sign, depth - NULL_MOV_REDUCTION - 1, true, depth_extend_count, ply + 1, node_count, seldepth);
-            state.undo_null_mov();
+        if let Some((hash_depth, hash_flag, hash_score, saved_hash_mov)) = self.hash_table.get(state.hash_key, ply) {
+            hash_mov = saved_hash_mov;
 
-            if score * player_sign >= beta * player_sign {
-                return beta
+            if ply > 0 && hash_depth >= depth {
+                match hash_flag {
+                    HASH_TYPE_EXACT => {
+                        pv_table[0] = hash_mov;
+                        return hash_score
+                    },
+                    HASH_TYPE_LOWER if hash_score * player_sign >= beta * player_sign => return hash_score,
+                    HASH_TYPE_UPPER if hash_score * player_sign <= alpha * player_sign => return hash_score,
+                    _ => (),
+                }
             }
         }
 
-        let (cap_list, non_cap_list) = self.mov_generator.gen_reg_mov_list(state, false);
-
-        let mut scored_capture_list = Vec::new();
-        let squares = state.squares;
+        let original_alpha = alpha;
 
-        for cap in cap_list {
-            let (from, to, _tp, promo) = util::decode_u32_mov(cap);
+        if !in_check && (!depth_reduced || depth_extend_count > 1) && depth >= NULL_MOV_MIN_DEPTH {
+            let mut next_pv_table = [0; PV_TRACK_LENGTH];
 
-            let exchange_score = eval::val_of(squares[to]) - eval::val_of(squares[from]) + eval::val_of(promo);
+            let undo_info = state.do_null_mov();
+            let score = self.ab_search(state, &mut next_pv_table, beta, beta - player_sign, depth - self.null_mov_reduction - 1, true, depth_extend_count, ply + 1, 0, node_count, seldepth);
+            state.undo_null_mov(undo_info);
 
-            if exchange_score > eval::EQUAL_EXCHANGE_VAL || depth == 1 {
-                scored_capture_list.push((exchange_score, cap));
-            } else {
-                let see_score = self.see(state, to, squares[from]) * player_sign + eval::val_of(promo);
-                scored_capture_list.push((see_score, cap));
+            if score * player_sign >= beta * player_sign {
+                return beta
             }
         }
 
-        scored_capture_list.sort_by(|(score_a, _), (score_b, _)| {
-            score_b.partial_cmp(&score_a).unwrap()
-        });
-
-        for (_score, cap) in scored_capture_list {
-            match self.search_mov(state, pv_table, cap, true, alpha, beta, depth, depth_reduced, depth_extend_count, ply, player_sign, node_count, seldepth) {
-                Beta(score) => return score,
-                Alpha(score) => {
-                    alpha = score;
-                },
-                Noop => (),
-            }
+        if hash_mov != 0 && !self.mov_generator.is_mov_valid(state, hash_mov) {
+            hash_mov = 0;
         }
 
-        let mut refutation_mov = 0;
+        let mut killer_mov_list = [0; KILLER_SLOT_COUNT + 1];
+        let mut killer_mov_count = 0;
+
         if ply > 0 && !depth_reduced {
-            let (_refutation_score, saved_refutation_mov) = self.refutation_table[ply as usize];
-            refutation_mov = saved_refutation_mov;
-
-            if refutation_mov != 0 && non_cap_list.contains(&refutation_mov) {
-                match self.search_mov(state, pv_table, refutation_mov, false, alpha, beta, depth, depth_reduced, depth_extend_count, ply, player_sign, node_count, seldepth) {
-                    Beta(score) => return score,
-                    Alpha(score) => {
-                        alpha = score;
-                    },
-                    Noop => (),
-                }
+            for killer_mov in self.killer_table[ply as usize].iter().filter(|killer_mov| **killer_mov != 0) {
+                killer_mov_list[killer_mov_count] = *killer_mov;
+                killer_mov_count += 1;
             }
         }
 
-        let mut scored_non_cap_list = Vec::new();
-
-        for non_cap in non_cap_list {
-            if non_cap == refutation_mov {
-                continue
-            }
-
-            let (from, to, _tp, promo) = util::decode_u32_mov(non_cap);
-            if promo != 0 {
-                scored_non_cap_list.push((MAX_HISTORY_SCORE, non_cap));
-                continue
-            }
-
-            let history_score = self.history_table[from][to];
-            scored_non_cap_list.push((history_score, non_cap));
+        let counter_mov = self.get_counter_mov(state, last_mov);
+        if counter_mov != 0 && !killer_mov_list[..killer_mov_count].contains(&counter_mov) {
+            killer_mov_list[killer_mov_count] = counter_mov;
+            killer_mov_count += 1;
         }
 
-        scored_non_cap_list.sort_by(|(score_a, _), (score_b, _)| {
-            score_b.partial_cmp(&score_a).unwrap()
-        });
+        let mut mov_picker = MovePicker::new(hash_mov, &killer_mov_list[..killer_mov_count], depth, player_sign);
+        let mut searched_mov_count = 0;
 
-        for (_score, non_cap) in scored_non_cap_list {
-            match self.search_mov(state, pv_table, non_cap, false, alpha, beta, depth, depth_reduced, depth_extend_count, ply, player_sign, node_count, seldepth) {
+        while let Some((mov, is_capture)) = mov_picker.next(self, state) {
+            match self.search_mov(state, pv_table, mov, is_capture, alpha, beta, depth, depth_reduced, depth_extend_count, ply, last_mov, player_sign, &mut searched_mov_count, node_count, seldepth) {
                 Beta(score) => return score,
                 Alpha(score) => {
                     alpha = score;
                 },
                 Noop => (),
             }
-        }
 
-        let castle_list = self.mov_generator.gen_castle_mov_list(state);
-        for cas_mov in castle_list {
-            match self.search_mov(state, pv_table, cas_mov, false, alpha, beta, depth, depth_reduced, depth_extend_count, ply, player_sign, node_count, seldepth) {
-                Beta(score) => return score,
-                Alpha(score) => {
-                    alpha = score;
-                },
-                Noop => (),
+            if self.abort {
+                return alpha
             }
         }
 
@@ -265,8 +662,16 @@ impl SearchEngine {
                 pv_table[0] = 0;
             }
 
-            if !in_check && self.in_stale_mate(state) {
-                return 0
+            if !in_check && self.mov_generator.gen_legal_mov_list(state).is_empty() {
+                return self.draw_score
+            }
+        }
+
+        if !self.abort && (ply > 0 || self.excluded_root_mov_list.is_empty()) {
+            if alpha * player_sign > original_alpha * player_sign {
+                self.hash_table.set(state.hash_key, depth, ply, HASH_TYPE_EXACT, alpha, pv_table[0]);
+            } else {
+                self.hash_table.set(state.hash_key, depth, ply, HASH_TYPE_UPPER, alpha, hash_mov);
             }
         }
 
@@ -274,7 +679,12 @@ impl SearchEngine {
     }
 
     #[inline]
-    fn search_mov(&mut self, state: &mut State, pv_table: &mut [u32], mov: u32, is_capture: bool, alpha: i32, beta: i32, depth: u8, depth_reduced: bool, depth_extend_count: u8, ply: u8, player_sign: i32, node_count: &mut u64, seldepth: &mut u8) -> SearchMovResult {
+    #[allow(clippy::too_many_arguments)]
+    fn search_mov(&mut self, state: &mut State, pv_table: &mut [u32], mov: u32, is_capture: bool, alpha: i32, beta: i32, depth: u8, depth_reduced: bool, depth_extend_count: u8, ply: u8, last_mov: u32, player_sign: i32, searched_mov_count: &mut u8, node_count: &mut u64, seldepth: &mut u8) -> SearchMovResult {
+        if ply == 0 && self.is_root_mov_skipped(mov) {
+            return Noop
+        }
+
         let (from, to, tp, promo) = util::decode_u32_mov(mov);
 
         if is_capture {
@@ -285,27 +695,43 @@ impl SearchEngine {
         }
 
         let mut next_pv_table = [0; PV_TRACK_LENGTH];
+        *searched_mov_count += 1;
+
+        let undo_info = state.do_mov(from, to, tp, promo);
+
+        let score = if self.pvs_enabled && *searched_mov_count > 1 {
+            let score = self.ab_search(state, &mut next_pv_table, alpha + player_sign, alpha, depth - 1, depth_reduced, depth_extend_count, ply + 1, mov, node_count, seldepth);
+
+            if score * player_sign > alpha * player_sign && score * player_sign < beta * player_sign {
+                next_pv_table = [0; PV_TRACK_LENGTH];
+                self.ab_search(state, &mut next_pv_table, beta, alpha, depth - 1, depth_reduced, depth_extend_count, ply + 1, mov, node_count, seldepth)
+            } else {
+                score
+            }
+        } else {
+            self.ab_search(state, &mut next_pv_table, beta, alpha, depth - 1, depth_reduced, depth_extend_count, ply + 1, mov, node_count, seldepth)
+        };
+
+        state.undo_mov(from, to, tp, undo_info);
 
-        state.do_mov(from, to, tp, promo);
-        let score = self.ab_search(state, &mut next_pv_table, beta, alpha, depth - 1, depth_reduced, depth_extend_count, ply + 1, node_count, seldepth);
-        state.undo_mov(from, to, tp);
+        if self.abort {
+            return Noop
+        }
 
         let history_improvement = depth as u64;
 
         if score * player_sign >= beta * player_sign {
-            if !is_capture && !depth_reduced {
-                let (refutation_score, refutation_mov) = self.refutation_table[ply as usize];
-                if refutation_mov == 0 {
-                    self.refutation_table[ply as usize] = (score, mov);
-                } else if score * player_sign > refutation_score * player_sign {
-                    self.refutation_table[ply as usize] = (score, mov);
+            if !is_capture {
+                if !depth_reduced {
+                    self.add_killer_mov(ply, mov);
                 }
-            }
 
-            if !is_capture {
-                self.history_table[from][to] = self.history_table[from][to] + history_improvement * history_improvement;
+                self.set_counter_mov(state, last_mov, mov);
+                self.history_table[from][to] += history_improvement * history_improvement;
             }
 
+            self.hash_table.set(state.hash_key, depth, ply, HASH_TYPE_LOWER, score, mov);
+
             return Beta(score)
         }
 
@@ -314,7 +740,7 @@ impl SearchEngine {
             pv_table[1..PV_TRACK_LENGTH].copy_from_slice(&next_pv_table[0..PV_TRACK_LENGTH-1]);
             
             if !is_capture {
-                self.history_table[from][to] = self.history_table[from][to] + history_improvement;
+                self.history_table[from][to] += history_improvement;
             }
 
             return Alpha(score)
@@ -323,44 +749,42 @@ impl SearchEngine {
         Noop
     }
 
-    #[inline]
-    fn in_stale_mate(&self, state: &mut State) -> bool {
-        let (cap_list, non_cap_list) = self.mov_generator.gen_reg_mov_list(state, false);
+    fn add_killer_mov(&mut self, ply: u8, mov: u32) {
+        let killer_movs = &mut self.killer_table[ply as usize];
 
-        for cap in cap_list {
-            let (from, to, tp, promo) = util::decode_u32_mov(cap);
-            state.do_mov(from, to, tp, promo);
-            state.player = def::get_opposite_player(state.player);
+        if killer_movs[0] != mov {
+            killer_movs[1] = killer_movs[0];
+            killer_movs[0] = mov;
+        }
+    }
 
-            if !self.mov_generator.is_in_check(state) {
-                state.player = def::get_opposite_player(state.player);
-                state.undo_mov(from, to, tp);
-                return false
-            }
+    fn get_counter_mov(&self, state: &State, last_mov: u32) -> u32 {
+        if last_mov == 0 {
+            return 0
+        }
+
+        let (_from, to, _tp, _promo) = util::decode_u32_mov(last_mov);
+        self.counter_mov_table[state.squares[to] as usize][to]
+    }
 
-            state.player = def::get_opposite_player(state.player);
-            state.undo_mov(from, to, tp);
+    fn set_counter_mov(&mut self, state: &State, last_mov: u32, mov: u32) {
+        if last_mov == 0 {
+            return
         }
 
-        for non_cap in non_cap_list {
-            let (from, to, tp, promo) = util::decode_u32_mov(non_cap);
-            state.do_mov(from, to, tp, promo);
-            state.player = def::get_opposite_player(state.player);
+        let (_from, to, _tp, _promo) = util::decode_u32_mov(last_mov);
+        self.counter_mov_table[state.squares[to] as usize][to] = mov;
+    }
 
-            if !self.mov_generator.is_in_check(state) {
-                state.player = def::get_opposite_player(state.player);
-                state.undo_mov(from, to, tp);
-                return false
+    fn age_history_table(&mut self) {
+        for history_scores in self.history_table.iter_mut() {
+            for history_score in history_scores.iter_mut() {
+                *history_score >>= HISTORY_AGE_SHIFT;
             }
-
-            state.player = def::get_opposite_player(state.player);
-            state.undo_mov(from, to, tp);
         }
-
-        true
     }
 
-    pub fn q_search(&self, state: &mut State, mut alpha: i32, beta: i32, ply: u8, seldepth: &mut u8) -> i32 {
+    fn q_search(&self, state: &mut State, mut alpha: i32, beta: i32, ply: u8, seldepth: &mut u8) -> i32 {
         if ply > *seldepth {
             *seldepth = ply;
         }
@@ -394,20 +818,16 @@ impl SearchEngine {
         }
 
         let squares = state.squares;
-        let mut scored_cap_list = Vec::new();
+        let mut scored_cap_list = MoveList::new();
 
-        for cap in cap_list {
-            let (from, to, _tp, promo) = util::decode_u32_mov(cap);
+        for cap in &cap_list {
+            let (from, to, _tp, promo) = util::decode_u32_mov(*cap);
 
             let exchange_score = eval::val_of(squares[to]) - eval::val_of(squares[from]) + eval::val_of(promo);
-            scored_cap_list.push((exchange_score, cap));
+            scored_cap_list.push_scored(*cap, exchange_score as i64);
         }
 
-        scored_cap_list.sort_by(|(score_a, _), (score_b, _)| {
-            score_b.partial_cmp(&score_a).unwrap()
-        });
-
-        for (_score, cap) in scored_cap_list {
+        while let Some(cap) = scored_cap_list.pick_best() {
             let (from, to, tp, promo) = util::decode_u32_mov(cap);
 
             let captured_piece = state.squares[to];
@@ -415,9 +835,9 @@ impl SearchEngine {
                 return player_sign * (eval::K_VAL - ply as i32)
             }
 
-            state.do_mov(from, to, tp, promo);
+            let undo_info = state.do_mov(from, to, tp, promo);
             let score = self.q_search(state, beta, alpha, ply + 1, seldepth);
-            state.undo_mov(from, to, tp);
+            state.undo_mov(from, to, tp, undo_info);
 
             if score * player_sign >= beta * player_sign {
                 return score
@@ -442,9 +862,9 @@ impl SearchEngine {
         };
 
         let w_attacker_list = if player_sign > 0 {
-            let mut attacker_list = Vec::new();
+            let mut attacker_list = AttackerList::new();
             let mut found_init_attacker = false;
-            for attacker in &generated_w_attacker_list {
+            for attacker in generated_w_attacker_list.iter() {
                 let attacker = *attacker;
                 if attacker == initial_attacker {
                     found_init_attacker = true;
@@ -462,9 +882,9 @@ impl SearchEngine {
         };
 
         let b_attacker_list = if player_sign < 0 {
-            let mut attacker_list = Vec::new();
+            let mut attacker_list = AttackerList::new();
             let mut found_init_attacker = false;
-            for attacker in &generated_b_attacker_list {
+            for attacker in generated_b_attacker_list.iter() {
                 let attacker = *attacker;
                 if attacker == initial_attacker {
                     found_init_attacker = true;
@@ -481,10 +901,10 @@ impl SearchEngine {
             generated_b_attacker_list
         };
 
-        eval::val_of(state.squares[index]) * player_sign + self.simulate_exchange(player_sign * -1, w_attacker_list, b_attacker_list, 0, 0, initial_attacker)
+        eval::val_of(state.squares[index]) * player_sign + self.simulate_exchange(-player_sign, &w_attacker_list, &b_attacker_list, 0, 0, initial_attacker)
     }
 
-    fn simulate_exchange(&self, player_sign: i32, w_attacker_list: Vec<u8>, b_attacker_list: Vec<u8>, mut w_attacker_index: usize, mut b_attacker_index: usize, last_attacker: u8) -> i32 {
+    fn simulate_exchange(&self, player_sign: i32, w_attacker_list: &[u8], b_attacker_list: &[u8], mut w_attacker_index: usize, mut b_attacker_index: usize, last_attacker: u8) -> i32 {
         if (player_sign > 0 && w_attacker_index == w_attacker_list.len()) || (player_sign < 0 && b_attacker_index == b_attacker_list.len()) {
             return 0
         }
@@ -500,7 +920,7 @@ impl SearchEngine {
         };
 
         let sim_score =  player_sign * eval::val_of(last_attacker)
-            + self.simulate_exchange(player_sign * -1, w_attacker_list, b_attacker_list, w_attacker_index, b_attacker_index, next_attacker);
+            + self.simulate_exchange(-player_sign, w_attacker_list, b_attacker_list, w_attacker_index, b_attacker_index, next_attacker);
 
         if sim_score * player_sign > 0 {
             sim_score
@@ -518,10 +938,447 @@ mod tests {
         util,
     };
 
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    thread_local! {
+        static ALLOC_COUNT: Cell<u64> = const { Cell::new(0) };
+    }
+
+    struct CountingAllocator;
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOC_COUNT.try_with(|alloc_count| alloc_count.set(alloc_count.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let _ = ALLOC_COUNT.try_with(|alloc_count| alloc_count.set(alloc_count.get() + 1));
+            System.realloc(ptr, layout, new_size)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    const MAX_ALLOC_COUNT_PER_ITER: u64 = 32;
+    const PVS_TEST_WINDOW_SIZE: i32 = 500;
+
+    fn find_mov(state: &State, mov_str: &str) -> u32 {
+        *MoveGenerator::new().gen_legal_mov_list(state).iter().find(|mov| util::format_mov(**mov) == mov_str).unwrap()
+    }
+
+    fn movetime_limits(movetime: u128) -> SearchLimits {
+        SearchLimits {
+            movetime: Some(movetime),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_search_limits_time() {
+        let search_limits = SearchLimits {
+            wtime: Some(60000),
+            btime: Some(30000),
+            winc: Some(1000),
+            binc: Some(500),
+            movestogo: Some(10),
+            ..Default::default()
+        };
+
+        assert_eq!(6950, search_limits.get_max_time_millis(def::PLAYER_W, 50));
+        assert_eq!(3450, search_limits.get_max_time_millis(def::PLAYER_B, 50));
+
+        let search_limits = SearchLimits {
+            wtime: Some(60000),
+            btime: Some(1000),
+            binc: Some(2000),
+            ..Default::default()
+        };
+
+        assert_eq!(3000, search_limits.get_max_time_millis(def::PLAYER_W, 0));
+        assert_eq!(950, search_limits.get_max_time_millis(def::PLAYER_B, 50));
+
+        assert_eq!(450, movetime_limits(500).get_max_time_millis(def::PLAYER_B, 50));
+        assert_eq!(0, movetime_limits(30).get_max_time_millis(def::PLAYER_B, 50));
+        assert_eq!(u128::MAX, SearchLimits::default().get_max_time_millis(def::PLAYER_W, 50));
+        assert_eq!(u128::MAX, SearchLimits { infinite: true, wtime: Some(100), ..Default::default() }.get_max_time_millis(def::PLAYER_W, 50));
+    }
+
+    #[test]
+    fn test_search_limits_depth() {
+        assert_eq!(MAX_DEPTH, SearchLimits::default().get_max_depth());
+        assert_eq!(6, SearchLimits { depth: Some(6), ..Default::default() }.get_max_depth());
+        assert_eq!(4, SearchLimits { mate: Some(2), ..Default::default() }.get_max_depth());
+        assert_eq!(3, SearchLimits { depth: Some(3), mate: Some(2), ..Default::default() }.get_max_depth());
+    }
+
+    #[test]
+    fn test_search_with_limits() {
+        let mut state = State::new("r1b3kr/3pR1p1/ppq4p/5P2/4Q3/B7/P5PP/5RK1 w - - 1 0");
+        let mut search_engine = SearchEngine::new(16);
+
+        let best_mov = search_engine.search(&mut state, &SearchLimits { depth: Some(1), ..Default::default() }).best_mov;
+        assert_ne!(0, best_mov);
+
+        let best_mov = search_engine.search(&mut state, &movetime_limits(0)).best_mov;
+        assert_ne!(0, best_mov);
+
+        let best_mov = search_engine.search(&mut state, &SearchLimits { nodes: Some(1), ..Default::default() }).best_mov;
+        assert_ne!(0, best_mov);
+
+        let searchmoves = vec![find_mov(&state, "e4d3")];
+        let best_mov = search_engine.search(&mut state, &SearchLimits { depth: Some(3), searchmoves, ..Default::default() }).best_mov;
+        assert_eq!("e4d3", util::format_mov(best_mov));
+
+        let best_mov = search_engine.search(&mut state, &SearchLimits { mate: Some(5), ..Default::default() }).best_mov;
+        let (from, to, _, _) = util::decode_u32_mov(best_mov);
+        assert_eq!(from, util::map_sqr_notation_to_index("e7"));
+        assert_eq!(to, util::map_sqr_notation_to_index("g7"));
+    }
+
+    #[test]
+    fn test_search_result() {
+        let mut state = State::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let mut search_engine = SearchEngine::new(16);
+
+        let search_result = search_engine.search(&mut state, &SearchLimits { depth: Some(4), ..Default::default() });
+        assert_eq!(4, search_result.depth);
+        assert_ne!(0, search_result.best_mov);
+        assert_eq!(search_result.best_mov, search_result.pv[0]);
+        assert!(search_result.node_count > 0);
+        assert!(search_result.score.abs() < eval::ADVANCE_VAL);
+
+        let mut state = State::new("r1b3kr/3pR1p1/ppq4p/5P2/4Q3/B7/P5PP/5RK1 w - - 1 0");
+        let search_result = search_engine.search(&mut state, &SearchLimits { mate: Some(5), ..Default::default() });
+        assert!(search_result.score > eval::TERM_VAL);
+        assert_eq!(search_result.best_mov, search_result.pv[0]);
+    }
+
+    #[test]
+    fn test_search_material_draw() {
+        let mut state = State::new("8/8/4k3/3b4/8/3K4/8/5B2 w - - 0 1");
+        let mut search_engine = SearchEngine::new(16);
+
+        let search_result = search_engine.search(&mut state, &SearchLimits { depth: Some(4), ..Default::default() });
+        assert_eq!(0, search_result.score);
+    }
+
+    #[test]
+    fn test_search_multi_pv() {
+        let mut search_engine = SearchEngine::new(16);
+        search_engine.set_option(EngineOption::MultiPv(3));
+
+        let mut state = State::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let search_result = search_engine.search(&mut state, &SearchLimits { depth: Some(4), ..Default::default() });
+
+        assert_eq!(3, search_result.pv_line_list.len());
+        assert_eq!(search_result.best_mov, search_result.pv_line_list[0].mov);
+        assert_eq!(search_result.score, search_result.pv_line_list[0].score);
+
+        for (index, pv_line) in search_result.pv_line_list.iter().enumerate() {
+            assert_eq!(pv_line.mov, pv_line.pv[0]);
+            assert!(search_result.pv_line_list[index + 1..].iter().all(|other_pv_line| other_pv_line.mov != pv_line.mov && other_pv_line.score <= pv_line.score));
+        }
+
+        let mut state = State::new("7k/8/8/8/8/8/6q1/7K w - - 0 1");
+        let search_result = search_engine.search(&mut state, &SearchLimits { depth: Some(4), ..Default::default() });
+
+        assert_eq!(1, search_result.pv_line_list.len());
+        assert_eq!("h1g2", util::format_mov(search_result.best_mov));
+    }
+
+    #[test]
+    fn test_search_contempt() {
+        let mut search_engine = SearchEngine::new(16);
+        search_engine.set_option(EngineOption::Contempt(20));
+
+        let mut state = State::new("8/8/4k3/3b4/8/3K4/8/5B2 w - - 0 1");
+        let search_result = search_engine.search(&mut state, &SearchLimits { depth: Some(4), ..Default::default() });
+        assert_eq!(-20, search_result.score);
+
+        let mut state = State::new("8/8/4k3/3b4/8/3K4/8/5B2 b - - 0 1");
+        let search_result = search_engine.search(&mut state, &SearchLimits { depth: Some(4), ..Default::default() });
+        assert_eq!(-20, search_result.score);
+
+        let mut w_state = State::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        search_engine.search(&mut w_state, &SearchLimits { depth: Some(3), ..Default::default() });
+        assert!(search_engine.hash_table.get(w_state.hash_key, 0).is_some());
+
+        let mut w_state_2 = State::new("rnbqkbnr/pppppppp/8/8/8/5N2/PPPPPPPP/RNBQKB1R w KQkq - 0 1");
+        search_engine.search(&mut w_state_2, &SearchLimits { depth: Some(1), ..Default::default() });
+        assert!(search_engine.hash_table.get(w_state.hash_key, 0).is_some());
+
+        let mut b_state = State::new("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+        search_engine.search(&mut b_state, &SearchLimits { depth: Some(1), ..Default::default() });
+        assert!(search_engine.hash_table.get(w_state.hash_key, 0).is_none());
+    }
+
+    #[test]
+    fn test_search_pvs_node_count() {
+        let fen_list = [
+            ("2k2r2/pp2br2/1np1p2q/2NpP2p/2PP2p1/1P1N4/P3Q1PP/3R1R1K b - - 8 27", false),
+            ("r3r1k1/ppqb1ppp/8/4p1NQ/8/2P5/PP3PPP/R3R1K1 b - - 0 1", false),
+            ("3rr3/2pq2pk/p2p1pnp/8/2QBPP2/1P6/P5PP/4RRK1 b - - 0 1", false),
+            ("8/8/1r2b2p/8/8/2p5/2kR4/K7 b - - 3 56", false),
+            ("4r1k1/pp1Q1ppp/3B4/q2p4/5P1P/P3PbPK/1P1r4/2R5 b - - 3 5", true),
+            ("r5rk/2p1Nppp/3p3P/pp2p1P1/4P3/2qnPQK1/8/R6R w - - 1 0", true),
+            ("r1b3kr/3pR1p1/ppq4p/5P2/4Q3/B7/P5PP/5RK1 w - - 1 0", true),
+            ("1r2k1r1/pbppnp1p/1b3P2/8/Q7/B1PB1q2/P4PPP/3R2K1 w - - 1 0", true),
+            ("8/8/8/5p1p/3k1P1P/5K2/8/8 b - - 1 59", false),
+        ];
+
+        let mut total_node_count = 0;
+        let mut total_pvs_node_count = 0;
+
+        for (fen, is_mate) in fen_list.iter() {
+            let mut search_engine = SearchEngine::new(16);
+            search_engine.set_option(EngineOption::AspirationWindow(PVS_TEST_WINDOW_SIZE));
+            let search_result = search_engine.search(&mut State::new(fen), &SearchLimits { depth: Some(6), ..Default::default() });
+
+            let mut pvs_search_engine = SearchEngine::new(16);
+            pvs_search_engine.set_option(EngineOption::AspirationWindow(PVS_TEST_WINDOW_SIZE));
+            pvs_search_engine.set_option(EngineOption::PvsEnabled(true));
+            let pvs_search_result = pvs_search_engine.search(&mut State::new(fen), &SearchLimits { depth: Some(6), ..Default::default() });
+
+            println!("{} nodes {} pvs nodes {}", fen, search_result.node_count, pvs_search_result.node_count);
+
+            if *is_mate {
+                assert_eq!(search_result.best_mov, pvs_search_result.best_mov);
+                assert_eq!(search_result.score, pvs_search_result.score);
+            }
+
+            total_node_count += search_result.node_count;
+            total_pvs_node_count += pvs_search_result.node_count;
+        }
+
+        println!("total nodes {} pvs nodes {}", total_node_count, total_pvs_node_count);
+        assert!(total_pvs_node_count < total_node_count);
+    }
+
+    #[test]
+    fn test_search_stop_signal() {
+        let mut state = State::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let mut search_engine = SearchEngine::new(16);
+        let stop_signal = search_engine.get_stop_signal();
+
+        stop_signal.store(true, Ordering::Relaxed);
+        let best_mov = search_engine.search(&mut state, &SearchLimits { infinite: true, ..Default::default() }).best_mov;
+        assert_ne!(0, best_mov);
+
+        stop_signal.store(false, Ordering::Relaxed);
+        let stop_task = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(200));
+            stop_signal.store(true, Ordering::Relaxed);
+        });
+
+        let time_tracker = Instant::now();
+        let best_mov = search_engine.search(&mut state, &SearchLimits { infinite: true, ..Default::default() }).best_mov;
+        stop_task.join().unwrap();
+
+        assert_ne!(0, best_mov);
+        assert!(time_tracker.elapsed().as_millis() < 2000);
+    }
+
+    #[test]
+    fn test_search_node_limit() {
+        let mut state = State::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let mut search_engine = SearchEngine::new(16);
+
+        let search_result = search_engine.search(&mut state, &SearchLimits { nodes: Some(1), ..Default::default() });
+        assert_eq!(1, search_result.node_count);
+        assert!(MoveGenerator::new().gen_legal_mov_list(&state).contains(&search_result.best_mov));
+
+        let searchmoves = vec![find_mov(&state, "a2a3")];
+        let search_result = search_engine.search(&mut state, &SearchLimits { nodes: Some(10), searchmoves, ..Default::default() });
+        assert!(search_result.node_count <= 10);
+        assert_eq!("a2a3", util::format_mov(search_result.best_mov));
+    }
+
+    #[test]
+    fn test_search_no_legal_mov() {
+        let mut search_engine = SearchEngine::new(16);
+
+        let mut state = State::new("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+        let search_result = search_engine.search(&mut state, &SearchLimits { depth: Some(3), ..Default::default() });
+        assert_eq!(0, search_result.best_mov);
+        assert_eq!(0, search_result.score);
+
+        let mut state = State::new("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1");
+        let search_result = search_engine.search(&mut state, &SearchLimits { depth: Some(3), ..Default::default() });
+        assert_eq!(0, search_result.best_mov);
+        assert_eq!(-eval::K_VAL, search_result.score);
+    }
+
+    #[test]
+    fn test_search_full_window_fail_high() {
+        let mut state = State::new("7k/8/8/8/8/8/8/K6Q w - - 0 1");
+        let mut search_engine = SearchEngine::new(16);
+
+        let search_result = search_engine.search(&mut state, &SearchLimits { depth: Some(2), ..Default::default() });
+        assert_eq!(1, search_result.depth);
+    }
+
+    #[test]
+    fn test_search_ponderhit() {
+        let mut state = State::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let mut search_engine = SearchEngine::new(16);
+        let ponder_signal = search_engine.get_ponder_signal();
+
+        ponder_signal.store(true, Ordering::Relaxed);
+        let ponderhit_task = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(300));
+            ponder_signal.store(false, Ordering::Relaxed);
+        });
+
+        let time_tracker = Instant::now();
+        let search_result = search_engine.search(&mut state, &SearchLimits { movetime: Some(100), ponder: true, ..Default::default() });
+        ponderhit_task.join().unwrap();
+
+        let time_taken_millis = time_tracker.elapsed().as_millis();
+        assert!(time_taken_millis >= 300);
+        assert!(time_taken_millis < 2000);
+        assert_eq!(search_result.pv[1], search_result.ponder_mov);
+    }
+
+    #[test]
+    fn test_search_ponder_mov() {
+        let mut search_engine = SearchEngine::new(16);
+
+        let mut state = State::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let search_result = search_engine.search(&mut state, &SearchLimits { depth: Some(1), ..Default::default() });
+        assert_eq!(1, search_result.pv.len());
+        assert_ne!(0, search_result.ponder_mov);
+
+        let mut state = State::new("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let search_result = search_engine.search(&mut state, &SearchLimits { depth: Some(3), ..Default::default() });
+        assert_eq!("a1a8", util::format_mov(search_result.best_mov));
+        assert_eq!(0, search_result.ponder_mov);
+    }
+
+    #[test]
+    fn test_search_alloc_count() {
+        let mut state = State::new("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let mut search_engine = SearchEngine::new(1);
+        let searchmoves = vec![find_mov(&state, "e2a6"), find_mov(&state, "d5e6"), find_mov(&state, "e1g1")];
+        let search_limits = SearchLimits { depth: Some(7), searchmoves, ..Default::default() };
+
+        let alloc_count_before_search = ALLOC_COUNT.with(|alloc_count| alloc_count.get());
+        let search_result = search_engine.search(&mut state, &search_limits);
+        let alloc_count = ALLOC_COUNT.with(|alloc_count| alloc_count.get()) - alloc_count_before_search;
+
+        println!("nodes {} allocations {}", search_result.node_count, alloc_count);
+        assert!(search_result.node_count > 100000);
+        assert!(alloc_count < MAX_ALLOC_COUNT_PER_ITER * search_result.depth as u64);
+    }
+
+    #[test]
+    fn test_mov_picker() {
+        let state = State::new("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let search_engine = SearchEngine::new(1);
+        let mov_generator = MoveGenerator::new();
+
+        let (cap_list, non_cap_list) = mov_generator.gen_reg_mov_list(&state, false);
+        let castle_list = mov_generator.gen_castle_mov_list(&state);
+        let find_mov = |mov_list: &[u32], mov_str: &str| *mov_list.iter().find(|mov| util::format_mov(**mov) == mov_str).unwrap();
+
+        let hash_mov = find_mov(&cap_list, "d5e6");
+        let killer_mov_list = vec![find_mov(&non_cap_list, "a2a3"), find_mov(&castle_list, "e1g1"), hash_mov, util::encode_u32_mov(util::map_sqr_notation_to_index("e2"), util::map_sqr_notation_to_index("e4"), def::MOV_CR_ENP, 0)];
+
+        let mut mov_picker = MovePicker::new(hash_mov, &killer_mov_list, 4, 1);
+        assert_eq!(Some((hash_mov, true)), mov_picker.next(&search_engine, &state));
+        assert!(mov_picker.next(&search_engine, &state).unwrap().1);
+        assert!(mov_picker.stage == MovePickerStage::Cap);
+        assert_eq!(cap_list.len() - 2, mov_picker.scored_mov_list.len());
+
+        let mut mov_picker = MovePicker::new(hash_mov, &killer_mov_list, 4, 1);
+        let mut picked_mov_list = Vec::new();
+        while let Some(picked_mov) = mov_picker.next(&search_engine, &state) {
+            picked_mov_list.push(picked_mov);
+        }
+
+        let picked_cap_count = picked_mov_list.iter().filter(|(_mov, is_capture)| *is_capture).count();
+        assert_eq!(cap_list.len(), picked_cap_count);
+        assert!(picked_mov_list[..picked_cap_count].iter().all(|(_mov, is_capture)| *is_capture));
+        assert_eq!("a2a3", util::format_mov(picked_mov_list[picked_cap_count].0));
+        assert_eq!("e1g1", util::format_mov(picked_mov_list[picked_cap_count + 1].0));
+
+        let mut picked_mov_list: Vec<u32> = picked_mov_list.into_iter().map(|(mov, _is_capture)| mov).collect();
+        let mut expected_mov_list: Vec<u32> = cap_list.iter().chain(non_cap_list.iter()).chain(castle_list.iter()).copied().collect();
+        picked_mov_list.sort();
+        expected_mov_list.sort();
+        assert_eq!(expected_mov_list, picked_mov_list);
+    }
+
+    #[test]
+    fn test_killer_table() {
+        let mut search_engine = SearchEngine::new(1);
+        let e2e4 = util::encode_u32_mov(util::map_sqr_notation_to_index("e2"), util::map_sqr_notation_to_index("e4"), def::MOV_CR_ENP, 0);
+        let d2d4 = util::encode_u32_mov(util::map_sqr_notation_to_index("d2"), util::map_sqr_notation_to_index("d4"), def::MOV_CR_ENP, 0);
+        let g1f3 = util::encode_u32_mov(util::map_sqr_notation_to_index("g1"), util::map_sqr_notation_to_index("f3"), def::MOV_REG, 0);
+
+        search_engine.add_killer_mov(3, e2e4);
+        search_engine.add_killer_mov(3, e2e4);
+        assert_eq!([e2e4, 0], search_engine.killer_table[3]);
+
+        search_engine.add_killer_mov(3, d2d4);
+        assert_eq!([d2d4, e2e4], search_engine.killer_table[3]);
+
+        search_engine.add_killer_mov(3, g1f3);
+        assert_eq!([g1f3, d2d4], search_engine.killer_table[3]);
+        assert_eq!([0, 0], search_engine.killer_table[2]);
+
+        search_engine.add_killer_mov(u8::MAX, e2e4);
+        assert_eq!([e2e4, 0], search_engine.killer_table[u8::MAX as usize]);
+    }
+
+    #[test]
+    fn test_counter_mov_table() {
+        let mut state = State::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let mut search_engine = SearchEngine::new(1);
+        let e2e4 = util::encode_u32_mov(util::map_sqr_notation_to_index("e2"), util::map_sqr_notation_to_index("e4"), def::MOV_CR_ENP, 0);
+        let e7e5 = util::encode_u32_mov(util::map_sqr_notation_to_index("e7"), util::map_sqr_notation_to_index("e5"), def::MOV_CR_ENP, 0);
+
+        assert_eq!(0, search_engine.get_counter_mov(&state, 0));
+
+        state.do_mov(util::map_sqr_notation_to_index("e2"), util::map_sqr_notation_to_index("e4"), def::MOV_CR_ENP, 0);
+        search_engine.set_counter_mov(&state, e2e4, e7e5);
+        assert_eq!(e7e5, search_engine.get_counter_mov(&state, e2e4));
+        assert_eq!(e7e5, search_engine.counter_mov_table[def::WP as usize][util::map_sqr_notation_to_index("e4")]);
+
+        search_engine.reset();
+        assert_eq!(0, search_engine.get_counter_mov(&state, e2e4));
+    }
+
+    #[test]
+    fn test_history_aging() {
+        let mut state = State::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let mut search_engine = SearchEngine::new(1);
+
+        search_engine.search(&mut state, &SearchLimits { depth: Some(6), ..Default::default() });
+        let history_table = search_engine.history_table;
+        assert!(history_table.iter().flatten().any(|history_score| *history_score > 1));
+
+        search_engine.age_history_table();
+        for (from, history_scores) in search_engine.history_table.iter().enumerate() {
+            for (to, history_score) in history_scores.iter().enumerate() {
+                assert_eq!(history_table[from][to] >> HISTORY_AGE_SHIFT, *history_score);
+            }
+        }
+
+        search_engine.reset();
+        assert!(search_engine.history_table.iter().flatten().all(|history_score| *history_score == 0));
+    }
+
     #[test]
     fn test_see_1() {
         let state = State::new("4q1kr/ppn1rp1p/n1p1PB2/5P2/2B1Q2P/2N3p1/PPP1b1P1/4R2K b - - 1 1");
-        let search_engine = SearchEngine::new();
+        let search_engine = SearchEngine::new(16);
 
         assert_eq!(120, search_engine.see(&state, util::map_sqr_notation_to_index("e6"), def::BN));
         assert_eq!(-100, search_engine.see(&state, util::map_sqr_notation_to_index("e6"), def::BP));
@@ -531,7 +1388,7 @@ mod tests {
     #[test]
     fn test_see_2() {
         let state = State::new("r5kr/1b1pR1p1/ppq1N2p/5P1n/3Q4/B6B/P5PP/5RK1 w - - 1 1");
-        let search_engine = SearchEngine::new();
+        let search_engine = SearchEngine::new(16);
 
         assert_eq!(-530, search_engine.see(&state, util::map_sqr_notation_to_index("g7"), def::WQ));
         assert_eq!(100, search_engine.see(&state, util::map_sqr_notation_to_index("g7"), def::WN));
@@ -541,7 +1398,7 @@ mod tests {
     #[test]
     fn test_see_3() {
         let state = State::new("r2q1kn1/p2b1rb1/1p1p1pp1/2pPp3/1PP1Pn2/PRNBB1K1/3QNPPP/5R2 w - - 0 1");
-        let search_engine = SearchEngine::new();
+        let search_engine = SearchEngine::new(16);
 
         assert_eq!(100, search_engine.see(&state, util::map_sqr_notation_to_index("f4"), def::WN));
         assert_eq!(90, search_engine.see(&state, util::map_sqr_notation_to_index("f4"), def::WB));
@@ -551,7 +1408,7 @@ mod tests {
     #[test]
     fn test_see_4() {
         let state = State::new("r4kn1/p2bprb1/Bp1p1ppP/2pP4/1PP1Pn2/PRNB2K1/2QN1PPq/5R2 w - - 0 1");
-        let search_engine = SearchEngine::new();
+        let search_engine = SearchEngine::new(16);
 
         assert_eq!(-19680, search_engine.see(&state, util::map_sqr_notation_to_index("f4"), def::WK));
     }
@@ -559,7 +1416,7 @@ mod tests {
     #[test]
     fn test_see_5() {
         let state = State::new("rn1qkbnr/pppbpppp/8/3p4/4P3/5Q2/PPPP1PPP/RNB1KBNR w KQkq - 2 3");
-        let search_engine = SearchEngine::new();
+        let search_engine = SearchEngine::new(16);
 
         assert_eq!(100, search_engine.see(&state, util::map_sqr_notation_to_index("d5"), def::WP));
     }
@@ -567,7 +1424,7 @@ mod tests {
     #[test]
     fn test_q_search_1() {
         let mut state = State::new("r5kr/1b1pR1p1/p1q1N2p/5P1n/3Q4/B7/P5PP/5RK1 w - - 1 1");
-        let search_engine = SearchEngine::new();
+        let search_engine = SearchEngine::new(16);
 
         assert_eq!(70, search_engine.q_search(&mut state, -20000, 20000, 0, &mut 0));
     }
@@ -575,7 +1432,7 @@ mod tests {
     #[test]
     fn test_q_search_2() {
         let mut state = State::new("2k2r2/pp2br2/1np1p2q/2NpP2p/2PP2p1/1P1N4/P3Q1PP/3R1R1K b - - 8 27");
-        let search_engine = SearchEngine::new();
+        let search_engine = SearchEngine::new(16);
 
         assert_eq!(-35, search_engine.q_search(&mut state, 20000, -20000, 0, &mut 0));
     }
@@ -583,7 +1440,7 @@ mod tests {
     #[test]
     fn test_q_search_3() {
         let mut state = State::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
-        let search_engine = SearchEngine::new();
+        let search_engine = SearchEngine::new(16);
 
         assert_eq!(0, search_engine.q_search(&mut state, -20000, 20000, 0, &mut 0));
     }
@@ -591,17 +1448,17 @@ mod tests {
     #[test]
     fn test_q_search_4() {
         let mut state = State::new("2k5/pp2b3/1np1p3/2NpP2p/3P2p1/2PN4/PP4PP/5q1K w - - 8 27");
-        let search_engine = SearchEngine::new();
+        let search_engine = SearchEngine::new(16);
 
-        assert_eq!(-985, search_engine.q_search(&mut state, -20000, 20000, 0, &mut 0));
+        assert_eq!(-977, search_engine.q_search(&mut state, -20000, 20000, 0, &mut 0));
     }
 
     #[test]
     fn test_search_puzzle_1() {
         let mut state = State::new("2k2r2/pp2br2/1np1p2q/2NpP2p/2PP2p1/1P1N4/P3Q1PP/3R1R1K b - - 8 27");
-        let mut search_engine = SearchEngine::new();
+        let mut search_engine = SearchEngine::new(16);
 
-        let best_mov = search_engine.search(&mut state, 5500);
+        let best_mov = search_engine.search(&mut state, &movetime_limits(5500)).best_mov;
 
         let (from, to, _, _) = util::decode_u32_mov(best_mov);
         assert_eq!(from, util::map_sqr_notation_to_index("h6"));
@@ -611,9 +1468,9 @@ mod tests {
     #[test]
     fn test_search_puzzle_2() {
         let mut state = State::new("r3r1k1/ppqb1ppp/8/4p1NQ/8/2P5/PP3PPP/R3R1K1 b - - 0 1");
-        let mut search_engine = SearchEngine::new();
+        let mut search_engine = SearchEngine::new(16);
 
-        let best_mov = search_engine.search(&mut state, 5500);
+        let best_mov = search_engine.search(&mut state, &movetime_limits(5500)).best_mov;
 
         let (from, to, _, _) = util::decode_u32_mov(best_mov);
         assert_eq!(from, util::map_sqr_notation_to_index("d7"));
@@ -623,9 +1480,9 @@ mod tests {
     #[test]
     fn test_search_puzzle_3() {
         let mut state = State::new("3rr3/2pq2pk/p2p1pnp/8/2QBPP2/1P6/P5PP/4RRK1 b - - 0 1");
-        let mut search_engine = SearchEngine::new();
+        let mut search_engine = SearchEngine::new(16);
 
-        let best_mov = search_engine.search(&mut state, 15500);
+        let best_mov = search_engine.search(&mut state, &movetime_limits(15500)).best_mov;
 
         let (from, to, _, _) = util::decode_u32_mov(best_mov);
         assert_eq!(from, util::map_sqr_notation_to_index("e8"));
@@ -635,9 +1492,9 @@ mod tests {
     #[test]
     fn test_search_puzzle_4() {
         let mut state = State::new("8/8/1r2b2p/8/8/2p5/2kR4/K7 b - - 3 56");
-        let mut search_engine = SearchEngine::new();
+        let mut search_engine = SearchEngine::new(16);
 
-        let best_mov = search_engine.search(&mut state, 5500);
+        let best_mov = search_engine.search(&mut state, &movetime_limits(5500)).best_mov;
 
         let (from, to, _, _) = util::decode_u32_mov(best_mov);
         assert_eq!(from, util::map_sqr_notation_to_index("c2"));
@@ -647,9 +1504,9 @@ mod tests {
     #[test]
     fn test_search_puzzle_5() {
         let mut state = State::new("4r1k1/pp1Q1ppp/3B4/q2p4/5P1P/P3PbPK/1P1r4/2R5 b - - 3 5");
-        let mut search_engine = SearchEngine::new();
+        let mut search_engine = SearchEngine::new(16);
 
-        let best_mov = search_engine.search(&mut state, 5500);
+        let best_mov = search_engine.search(&mut state, &movetime_limits(5500)).best_mov;
 
         let (from, to, _, _) = util::decode_u32_mov(best_mov);
         assert_eq!(from, util::map_sqr_notation_to_index("d2"));
@@ -659,9 +1516,9 @@ mod tests {
     #[test]
     fn test_search_puzzle_6() {
         let mut state = State::new("r5rk/2p1Nppp/3p3P/pp2p1P1/4P3/2qnPQK1/8/R6R w - - 1 0");
-        let mut search_engine = SearchEngine::new();
+        let mut search_engine = SearchEngine::new(16);
 
-        let best_mov = search_engine.search(&mut state, 10000);
+        let best_mov = search_engine.search(&mut state, &movetime_limits(10000)).best_mov;
 
         let (from, to, _, _) = util::decode_u32_mov(best_mov);
         assert_eq!(from, util::map_sqr_notation_to_index("h6"));
@@ -671,9 +1528,9 @@ mod tests {
     #[test]
     fn test_search_puzzle_7() {
         let mut state = State::new("r1b3kr/3pR1p1/ppq4p/5P2/4Q3/B7/P5PP/5RK1 w - - 1 0");
-        let mut search_engine = SearchEngine::new();
+        let mut search_engine = SearchEngine::new(16);
 
-        let best_mov = search_engine.search(&mut state, 5500);
+        let best_mov = search_engine.search(&mut state, &movetime_limits(5500)).best_mov;
 
         let (from, to, _, _) = util::decode_u32_mov(best_mov);
         assert_eq!(from, util::map_sqr_notation_to_index("e7"));
@@ -683,9 +1540,9 @@ mod tests {
     #[test]
     fn test_search_puzzle_8() {
         let mut state = State::new("1r2k1r1/pbppnp1p/1b3P2/8/Q7/B1PB1q2/P4PPP/3R2K1 w - - 1 0");
-        let mut search_engine = SearchEngine::new();
+        let mut search_engine = SearchEngine::new(16);
 
-        let best_mov = search_engine.search(&mut state, 5500);
+        let best_mov = search_engine.search(&mut state, &movetime_limits(5500)).best_mov;
 
         let (from, to, _, _) = util::decode_u32_mov(best_mov);
         assert_eq!(from, util::map_sqr_notation_to_index("a4"));
@@ -695,9 +1552,9 @@ mod tests {
     #[test]
     fn test_search_puzzle_9() {
         let mut state = State::new("8/8/8/5p1p/3k1P1P/5K2/8/8 b - - 1 59");
-        let mut search_engine = SearchEngine::new();
+        let mut search_engine = SearchEngine::new(16);
 
-        let best_mov = search_engine.search(&mut state, 5500);
+        let best_mov = search_engine.search(&mut state, &movetime_limits(5500)).best_mov;
 
         let (from, to, _, _) = util::decode_u32_mov(best_mov);
         assert_eq!(from, util::map_sqr_notation_to_index("d4"));
@@ -707,9 +1564,9 @@ mod tests {
     #[test]
     fn test_search_endgame_1() {
         let mut state = State::new("8/2k5/2pR4/1pPp4/p7/P1P2P2/1P6/5K2 w - - 5 52");
-        let mut search_engine = SearchEngine::new();
+        let mut search_engine = SearchEngine::new(16);
 
-        let best_mov = search_engine.search(&mut state, 5500);
+        let best_mov = search_engine.search(&mut state, &movetime_limits(5500)).best_mov;
 
         let (from, to, _, _) = util::decode_u32_mov(best_mov);
         assert_eq!(from, util::map_sqr_notation_to_index("f3"));
@@ -719,9 +1576,9 @@ mod tests {
     #[test]
     fn test_search_endgame_2() {
         let mut state = State::new("7r/8/7P/4k3/4B3/4K2R/8/8 w - - 7 76");
-        let mut search_engine = SearchEngine::new();
+        let mut search_engine = SearchEngine::new(16);
 
-        let best_mov = search_engine.search(&mut state, 5500);
+        let best_mov = search_engine.search(&mut state, &movetime_limits(5500)).best_mov;
 
         let (from, to, _, _) = util::decode_u32_mov(best_mov);
         assert_eq!(from, util::map_sqr_notation_to_index("h6"));
@@ -731,9 +1588,9 @@ mod tests {
     #[test]
     fn test_search_endgame_3() {
         let mut state = State::new("8/8/8/3K4/8/5k2/5p2/8 b - - 9 83");
-        let mut search_engine = SearchEngine::new();
+        let mut search_engine = SearchEngine::new(16);
 
-        let best_mov = search_engine.search(&mut state, 5500);
+        let best_mov = search_engine.search(&mut state, &movetime_limits(5500)).best_mov;
 
         let (from, to, _, _) = util::decode_u32_mov(best_mov);
         assert_eq!(from, util::map_sqr_notation_to_index("f2"));
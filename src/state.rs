@@ -1,6 +1,9 @@
 use crate::{
     def,
+    eval::EvalSum,
+    mov_gen::MoveGenerator,
     util,
+    zob_keys,
 };
 
 use std::fmt;
@@ -9,26 +12,62 @@ const FEN_SQRS_INDEX: usize = 0;
 const FEN_PLAYER_INDEX: usize = 1;
 const FEN_CAS_RIGHTS_INDEX: usize = 2;
 const FEN_ENP_SQR_INDEX: usize = 3;
-const LAST_DUP_MOV_DISTANCE: usize = 5;
+const FEN_HALF_MOV_INDEX: usize = 4;
+const FEN_FULL_MOV_INDEX: usize = 5;
+const MIN_REP_DISTANCE: usize = 4;
 const MAX_NON_CAP_PLY_COUNT: u16 = 100;
+const MAX_PIECE_COUNT: usize = 16;
+const MAX_PAWN_COUNT: usize = 8;
+
+#[derive(Debug, PartialEq)]
+pub enum FenError {
+    Malformed,
+    KingCount,
+    TooManyPieces,
+    TooManyPawns,
+    PawnOnBackRank,
+    InvalidEnpSqr,
+    OpponentInCheck,
+}
 
-pub struct State {
-    pub squares: [u8; def::BOARD_SIZE],
-    pub player: u8,
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::Malformed => write!(f, "malformed fen"),
+            FenError::KingCount => write!(f, "each side must have exactly one king"),
+            FenError::TooManyPieces => write!(f, "a side has more than {} pieces", MAX_PIECE_COUNT),
+            FenError::TooManyPawns => write!(f, "a side has more than {} pawns", MAX_PAWN_COUNT),
+            FenError::PawnOnBackRank => write!(f, "pawn on the first or last rank"),
+            FenError::InvalidEnpSqr => write!(f, "no pawn can be taken en passant on the given square"),
+            FenError::OpponentInCheck => write!(f, "the side not to move is in check"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UndoInfo {
+    pub taken_piece: u8,
     pub cas_rights: u8,
     pub enp_square: usize,
     pub non_cap_mov_count: u16,
+    pub hash_key: u64,
+}
 
-    pub wk_index: usize,
-    pub bk_index: usize,
-
-    pub taken_piece_stack: Vec<u8>,
-    pub enp_sqr_stack: Vec<usize>,
-    pub cas_rights_stack: Vec<u8>,
-    pub history_mov_stack: Vec<(usize, usize, u8, u8)>,
-    pub non_cap_mov_count_stack: Vec<u16>,
-    pub wk_index_stack: Vec<usize>,
-    pub bk_index_stack: Vec<usize>,
+#[derive(Clone)]
+pub struct State {
+    pub(crate) squares: [u8; def::BOARD_SIZE],
+    pub(crate) player: u8,
+    pub(crate) cas_rights: u8,
+    pub(crate) enp_square: usize,
+    pub(crate) non_cap_mov_count: u16,
+    pub(crate) full_mov_count: u16,
+    pub(crate) hash_key: u64,
+
+    pub(crate) wk_index: usize,
+    pub(crate) bk_index: usize,
+    pub(crate) eval_sum: EvalSum,
+
+    pub(crate) hash_key_stack: Vec<u64>,
 }
 
 impl State {
@@ -38,116 +77,176 @@ impl State {
         let player = get_player_from_fen(fen_segment_list[FEN_PLAYER_INDEX]);
         let cas_rights = get_cas_rights_from_fen(fen_segment_list[FEN_CAS_RIGHTS_INDEX]);
         let enp_sqr = get_enp_sqr_from_fen(fen_segment_list[FEN_ENP_SQR_INDEX]);
+        let non_cap_mov_count = fen_segment_list.get(FEN_HALF_MOV_INDEX).and_then(|fen_mov_count_string| get_mov_count_from_fen(fen_mov_count_string)).unwrap_or(0);
+        let full_mov_count = fen_segment_list.get(FEN_FULL_MOV_INDEX).and_then(|fen_mov_count_string| get_mov_count_from_fen(fen_mov_count_string)).unwrap_or(1).max(1);
+        let hash_key = gen_hash_key(&squares, player, cas_rights, enp_sqr);
 
         State {
-            squares: squares,
-            player: player,
-            cas_rights: cas_rights,
+            squares,
+            player,
+            cas_rights,
             enp_square: enp_sqr,
-            non_cap_mov_count: 0,
-            wk_index: wk_index,
-            bk_index: bk_index,
-            
-            taken_piece_stack: Vec::new(),
-            enp_sqr_stack: Vec::new(),
-            cas_rights_stack: Vec::new(),
-            history_mov_stack: Vec::new(),
-            non_cap_mov_count_stack: Vec::new(),
-            wk_index_stack: Vec::new(),
-            bk_index_stack: Vec::new(),
+            non_cap_mov_count,
+            full_mov_count,
+            hash_key,
+            wk_index,
+            bk_index,
+            eval_sum: EvalSum::new(&squares),
+
+            hash_key_stack: Vec::new(),
         }
     }
 
-    pub fn is_draw(&self) -> bool {
+    pub fn get_hash_key(&self) -> u64 {
+        self.hash_key
+    }
+
+    pub fn to_fen(&self) -> String {
+        let enp_sqr_string = if self.enp_square == 0 {
+            "-".to_owned()
+        } else {
+            util::map_index_to_sqr_notation(self.enp_square)
+        };
+
+        let player_string = if self.player == def::PLAYER_W {
+            "w"
+        } else {
+            "b"
+        };
+
+        format!("{} {} {} {} {} {}",
+            get_fen_from_squares(&self.squares), player_string, get_fen_from_cas_rights(self.cas_rights), enp_sqr_string, self.non_cap_mov_count, self.full_mov_count)
+    }
+
+    pub fn is_draw(&self, ply: u8) -> bool {
         if self.non_cap_mov_count >= MAX_NON_CAP_PLY_COUNT {
             return true
         }
 
-        if (self.non_cap_mov_count as usize) < LAST_DUP_MOV_DISTANCE + 1 {
-            return false
-        }
+        let history_len = self.hash_key_stack.len();
+        let max_rep_distance = (self.non_cap_mov_count as usize).min(history_len);
+
+        let mut rep_count = 0;
+        let mut rep_distance = MIN_REP_DISTANCE;
+        while rep_distance <= max_rep_distance {
+            if self.hash_key_stack[history_len - rep_distance] == self.hash_key {
+                if rep_distance < ply as usize {
+                    return true
+                }
 
-        let history_len = self.history_mov_stack.len();
+                rep_count += 1;
 
-        let (from, to, mov_piece, taken_piece) = self.history_mov_stack[history_len-1];
-        let (last_from, last_to, last_mov_piece, last_taken_piece) = self.history_mov_stack[history_len-LAST_DUP_MOV_DISTANCE];
-        if from == last_from && to == last_to && mov_piece == last_mov_piece && taken_piece == last_taken_piece {
-            
-            let (from, to, mov_piece, taken_piece) = self.history_mov_stack[history_len-2];
-            let (last_from, last_to, last_mov_piece, last_taken_piece) = self.history_mov_stack[history_len-LAST_DUP_MOV_DISTANCE-1];
-            if from == last_from && to == last_to && mov_piece == last_mov_piece && taken_piece == last_taken_piece {
-                return true
+                if rep_count >= 2 {
+                    return true
+                }
             }
+
+            rep_distance += 2;
         }
 
         false
     }
 
-    pub fn do_null_mov(&mut self) {
-        self.enp_sqr_stack.push(self.enp_square);
+    fn get_undo_info(&self) -> UndoInfo {
+        UndoInfo {
+            taken_piece: 0,
+            cas_rights: self.cas_rights,
+            enp_square: self.enp_square,
+            non_cap_mov_count: self.non_cap_mov_count,
+            hash_key: self.hash_key,
+        }
+    }
+
+    pub fn do_null_mov(&mut self) -> UndoInfo {
+        let undo_info = self.get_undo_info();
+
+        self.hash_key_stack.push(self.hash_key);
+        self.hash_key ^= zob_keys::get_enp_sqr_key(self.enp_square) ^ zob_keys::get_player_key();
         self.enp_square = 0;
+        self.non_cap_mov_count = 0;
         self.player = def::get_opposite_player(self.player);
+
+        undo_info
     }
 
-    pub fn undo_null_mov(&mut self) {
-        self.enp_square = self.enp_sqr_stack.pop().unwrap();
+    pub fn undo_null_mov(&mut self, undo_info: UndoInfo) {
+        self.enp_square = undo_info.enp_square;
+        self.non_cap_mov_count = undo_info.non_cap_mov_count;
+        self.hash_key = undo_info.hash_key;
+        self.hash_key_stack.pop();
         self.player = def::get_opposite_player(self.player);
     }
 
-    pub fn do_mov(&mut self, from: usize, to: usize, mov_type: u8, promo: u8) {
-        self.cas_rights_stack.push(self.cas_rights);
-        self.enp_sqr_stack.push(self.enp_square);
-        self.history_mov_stack.push((from, to, self.squares[from], self.squares[to]));
-        self.non_cap_mov_count_stack.push(self.non_cap_mov_count);
-        self.wk_index_stack.push(self.wk_index);
-        self.bk_index_stack.push(self.bk_index);
+    pub fn do_mov(&mut self, from: usize, to: usize, mov_type: u8, promo: u8) -> UndoInfo {
+        let mut undo_info = self.get_undo_info();
+
+        self.hash_key_stack.push(self.hash_key);
+        self.hash_key ^= zob_keys::get_cas_rights_key(self.cas_rights) ^ zob_keys::get_enp_sqr_key(self.enp_square);
         self.enp_square = 0;
 
-        match mov_type {
+        undo_info.taken_piece = match mov_type {
             def::MOV_REG => self.do_reg_mov(from, to),
             def::MOV_PROMO => self.do_promo_mov(from, to, promo),
             def::MOV_CAS => self.do_cas_mov(to),
             def::MOV_ENP => self.do_enp_mov(from, to),
             def::MOV_CR_ENP => self.do_cr_enp_mov(from, to),
             _ => panic!("invalid mov type {}", mov_type),
+        };
+
+        if self.player == def::PLAYER_B {
+            self.full_mov_count += 1;
         }
 
         self.player = def::get_opposite_player(self.player);
+        self.hash_key ^= zob_keys::get_cas_rights_key(self.cas_rights) ^ zob_keys::get_enp_sqr_key(self.enp_square) ^ zob_keys::get_player_key();
+
+        undo_info
     }
 
-    pub fn undo_mov(&mut self, from: usize, to: usize, mov_type: u8) {
-        self.cas_rights = self.cas_rights_stack.pop().unwrap();
-        self.enp_square = self.enp_sqr_stack.pop().unwrap();
-        self.non_cap_mov_count = self.non_cap_mov_count_stack.pop().unwrap();
-        self.wk_index = self.wk_index_stack.pop().unwrap();
-        self.bk_index = self.bk_index_stack.pop().unwrap();
-        self.history_mov_stack.pop();
+    pub fn undo_mov(&mut self, from: usize, to: usize, mov_type: u8, undo_info: UndoInfo) {
+        self.cas_rights = undo_info.cas_rights;
+        self.enp_square = undo_info.enp_square;
+        self.non_cap_mov_count = undo_info.non_cap_mov_count;
+        self.hash_key = undo_info.hash_key;
+        self.hash_key_stack.pop();
 
         self.player = def::get_opposite_player(self.player);
 
+        if self.player == def::PLAYER_B {
+            self.full_mov_count -= 1;
+        }
+
         match mov_type {
-            def::MOV_REG => self.undo_reg_mov(from, to),
-            def::MOV_PROMO => self.undo_promo_mov(from, to),
+            def::MOV_REG => self.undo_reg_mov(from, to, undo_info.taken_piece),
+            def::MOV_PROMO => self.undo_promo_mov(from, to, undo_info.taken_piece),
             def::MOV_CAS => self.undo_cas_mov(to),
-            def::MOV_ENP => self.undo_enp_mov(from, to),
+            def::MOV_ENP => self.undo_enp_mov(from, to, undo_info.taken_piece),
             def::MOV_CR_ENP => self.undo_cr_enp_mov(from, to),
             _ => panic!("invalid mov type {}", mov_type),
         }
     }
 
-    fn do_reg_mov(&mut self, from: usize, to: usize) {
+    fn set_sqr(&mut self, index: usize, piece: u8) {
+        self.eval_sum.set_sqr(&mut self.squares, index, piece);
+    }
+
+    fn do_reg_mov(&mut self, from: usize, to: usize) -> u8 {
         let moving_piece = self.squares[from];
         let taken_piece = self.squares[to];
 
-        if taken_piece == 0 {
+        if taken_piece == 0 && !def::is_p(moving_piece) {
             self.non_cap_mov_count += 1;
         } else {
             self.non_cap_mov_count = 0;
         }
 
-        self.taken_piece_stack.push(taken_piece);
-        self.squares[to] = moving_piece;
-        self.squares[from] = 0;
+        self.set_sqr(to, moving_piece);
+        self.set_sqr(from, 0);
+
+        self.hash_key ^= zob_keys::get_piece_key(moving_piece, from) ^ zob_keys::get_piece_key(moving_piece, to);
+        if taken_piece != 0 {
+            self.hash_key ^= zob_keys::get_piece_key(taken_piece, to);
+        }
 
         if moving_piece == def::WR {
             if from == 0 {
@@ -174,46 +273,53 @@ impl State {
 
             self.bk_index = to;
         }
+
+        taken_piece
     }
 
-    fn undo_reg_mov(&mut self, from: usize, to: usize) {
+    fn undo_reg_mov(&mut self, from: usize, to: usize, taken_piece: u8) {
         let moving_piece = self.squares[to];
-        let taken_piece = self.taken_piece_stack.pop().unwrap();
 
-        self.squares[to] = taken_piece;
-        self.squares[from] = moving_piece;
+        self.set_sqr(to, taken_piece);
+        self.set_sqr(from, moving_piece);
+
+        if moving_piece == def::WK {
+            self.wk_index = from;
+        } else if moving_piece == def::BK {
+            self.bk_index = from;
+        }
     }
 
-    fn do_promo_mov(&mut self, from: usize, to: usize, promo: u8) {
+    fn do_promo_mov(&mut self, from: usize, to: usize, promo: u8) -> u8 {
+        let moving_piece = self.squares[from];
         let taken_piece = self.squares[to];
 
-        if taken_piece == 0 {
-            self.non_cap_mov_count += 1;
-        } else {
-            self.non_cap_mov_count = 0;
-        }
+        self.non_cap_mov_count = 0;
 
-        self.taken_piece_stack.push(taken_piece);
-        self.squares[to] = promo;
-        self.squares[from] = 0;
+        self.set_sqr(to, promo);
+        self.set_sqr(from, 0);
 
-        self.non_cap_mov_count = 0;
+        self.hash_key ^= zob_keys::get_piece_key(moving_piece, from) ^ zob_keys::get_piece_key(promo, to);
+        if taken_piece != 0 {
+            self.hash_key ^= zob_keys::get_piece_key(taken_piece, to);
+        }
+
+        taken_piece
     }
 
-    fn undo_promo_mov(&mut self, from: usize, to: usize) {
+    fn undo_promo_mov(&mut self, from: usize, to: usize, taken_piece: u8) {
         let moving_piece = if self.player == def::PLAYER_W {
             def::WP
         } else {
             def::BP
         };
-        let taken_piece = self.taken_piece_stack.pop().unwrap();
 
-        self.squares[to] = taken_piece;
-        self.squares[from] = moving_piece;
+        self.set_sqr(to, taken_piece);
+        self.set_sqr(from, moving_piece);
     }
 
-    fn do_cas_mov(&mut self, to: usize) {
-        self.non_cap_mov_count = 0;
+    fn do_cas_mov(&mut self, to: usize) -> u8 {
+        self.non_cap_mov_count += 1;
 
         if to == def::CAS_SQUARE_WK {
             self.cas_rights &= 0b0111;
@@ -223,10 +329,13 @@ impl State {
             let r_index = def::CAS_SQUARE_WK+1;
             let r_to_index = def::CAS_SQUARE_WK-1;
 
-            self.squares[k_index] = 0;
-            self.squares[r_index] = 0;
-            self.squares[r_to_index] = def::WR;
-            self.squares[def::CAS_SQUARE_WK] = def::WK;
+            self.set_sqr(k_index, 0);
+            self.set_sqr(r_index, 0);
+            self.set_sqr(r_to_index, def::WR);
+            self.set_sqr(def::CAS_SQUARE_WK, def::WK);
+
+            self.hash_key ^= zob_keys::get_piece_key(def::WK, k_index) ^ zob_keys::get_piece_key(def::WK, def::CAS_SQUARE_WK)
+                ^ zob_keys::get_piece_key(def::WR, r_index) ^ zob_keys::get_piece_key(def::WR, r_to_index);
         } else if to == def::CAS_SQUARE_BK {
             self.cas_rights &= 0b1101;
             self.bk_index = to;
@@ -235,10 +344,13 @@ impl State {
             let r_index = def::CAS_SQUARE_BK+1;
             let r_to_index = def::CAS_SQUARE_BK-1;
 
-            self.squares[k_index] = 0;
-            self.squares[r_index] = 0;
-            self.squares[r_to_index] = def::BR;
-            self.squares[def::CAS_SQUARE_BK] = def::BK;
+            self.set_sqr(k_index, 0);
+            self.set_sqr(r_index, 0);
+            self.set_sqr(r_to_index, def::BR);
+            self.set_sqr(def::CAS_SQUARE_BK, def::BK);
+
+            self.hash_key ^= zob_keys::get_piece_key(def::BK, k_index) ^ zob_keys::get_piece_key(def::BK, def::CAS_SQUARE_BK)
+                ^ zob_keys::get_piece_key(def::BR, r_index) ^ zob_keys::get_piece_key(def::BR, r_to_index);
         } else if to == def::CAS_SQUARE_WQ {
             self.cas_rights &= 0b1011;
             self.wk_index = to;
@@ -247,10 +359,13 @@ impl State {
             let r_index = def::CAS_SQUARE_WQ-2;
             let r_to_index = def::CAS_SQUARE_WQ+1;
 
-            self.squares[k_index] = 0;
-            self.squares[r_index] = 0;
-            self.squares[r_to_index] = def::WR;
-            self.squares[def::CAS_SQUARE_WQ] = def::WK;
+            self.set_sqr(k_index, 0);
+            self.set_sqr(r_index, 0);
+            self.set_sqr(r_to_index, def::WR);
+            self.set_sqr(def::CAS_SQUARE_WQ, def::WK);
+
+            self.hash_key ^= zob_keys::get_piece_key(def::WK, k_index) ^ zob_keys::get_piece_key(def::WK, def::CAS_SQUARE_WQ)
+                ^ zob_keys::get_piece_key(def::WR, r_index) ^ zob_keys::get_piece_key(def::WR, r_to_index);
         } else if to == def::CAS_SQUARE_BQ {
             self.cas_rights &= 0b1110;
             self.bk_index = to;
@@ -259,38 +374,47 @@ impl State {
             let r_index = def::CAS_SQUARE_BQ-2;
             let r_to_index = def::CAS_SQUARE_BQ+1;
 
-            self.squares[k_index] = 0;
-            self.squares[r_index] = 0;
-            self.squares[r_to_index] = def::BR;
-            self.squares[def::CAS_SQUARE_BQ] = def::BK;
+            self.set_sqr(k_index, 0);
+            self.set_sqr(r_index, 0);
+            self.set_sqr(r_to_index, def::BR);
+            self.set_sqr(def::CAS_SQUARE_BQ, def::BK);
+
+            self.hash_key ^= zob_keys::get_piece_key(def::BK, k_index) ^ zob_keys::get_piece_key(def::BK, def::CAS_SQUARE_BQ)
+                ^ zob_keys::get_piece_key(def::BR, r_index) ^ zob_keys::get_piece_key(def::BR, r_to_index);
         }
+
+        0
     }
 
     fn undo_cas_mov(&mut self, to: usize) {
         if to == def::CAS_SQUARE_WK {
-            self.squares[def::CAS_SQUARE_WK-2] = def::WK;
-            self.squares[def::CAS_SQUARE_WK+1] = def::WR;
-            self.squares[def::CAS_SQUARE_WK-1] = 0;
-            self.squares[def::CAS_SQUARE_WK] = 0;
+            self.wk_index = def::CAS_SQUARE_WK-2;
+            self.set_sqr(def::CAS_SQUARE_WK-2, def::WK);
+            self.set_sqr(def::CAS_SQUARE_WK+1, def::WR);
+            self.set_sqr(def::CAS_SQUARE_WK-1, 0);
+            self.set_sqr(def::CAS_SQUARE_WK, 0);
         } else if to == def::CAS_SQUARE_BK {
-            self.squares[def::CAS_SQUARE_BK-2] = def::BK;
-            self.squares[def::CAS_SQUARE_BK+1] = def::BR;
-            self.squares[def::CAS_SQUARE_BK-1] = 0;
-            self.squares[def::CAS_SQUARE_BK] = 0;
+            self.bk_index = def::CAS_SQUARE_BK-2;
+            self.set_sqr(def::CAS_SQUARE_BK-2, def::BK);
+            self.set_sqr(def::CAS_SQUARE_BK+1, def::BR);
+            self.set_sqr(def::CAS_SQUARE_BK-1, 0);
+            self.set_sqr(def::CAS_SQUARE_BK, 0);
         } else if to == def::CAS_SQUARE_WQ {
-            self.squares[def::CAS_SQUARE_WQ+2] = def::WK;
-            self.squares[def::CAS_SQUARE_WQ-2] = def::WR;
-            self.squares[def::CAS_SQUARE_WQ+1] = 0;
-            self.squares[def::CAS_SQUARE_WQ] = 0;
+            self.wk_index = def::CAS_SQUARE_WQ+2;
+            self.set_sqr(def::CAS_SQUARE_WQ+2, def::WK);
+            self.set_sqr(def::CAS_SQUARE_WQ-2, def::WR);
+            self.set_sqr(def::CAS_SQUARE_WQ+1, 0);
+            self.set_sqr(def::CAS_SQUARE_WQ, 0);
         } else if to == def::CAS_SQUARE_BQ {
-            self.squares[def::CAS_SQUARE_BQ+2] = def::BK;
-            self.squares[def::CAS_SQUARE_BQ-2] = def::BR;
-            self.squares[def::CAS_SQUARE_BQ+1] = 0;
-            self.squares[def::CAS_SQUARE_BQ] = 0;
+            self.bk_index = def::CAS_SQUARE_BQ+2;
+            self.set_sqr(def::CAS_SQUARE_BQ+2, def::BK);
+            self.set_sqr(def::CAS_SQUARE_BQ-2, def::BR);
+            self.set_sqr(def::CAS_SQUARE_BQ+1, 0);
+            self.set_sqr(def::CAS_SQUARE_BQ, 0);
         }
     }
 
-    fn do_enp_mov(&mut self, from: usize, to: usize) {
+    fn do_enp_mov(&mut self, from: usize, to: usize) -> u8 {
         let taken_index = if self.player == def::PLAYER_W {
             to - 16
         } else {
@@ -302,13 +426,17 @@ impl State {
         let moving_piece = self.squares[from];
         let taken_piece = self.squares[taken_index];
 
-        self.taken_piece_stack.push(taken_piece);
-        self.squares[to] = moving_piece;
-        self.squares[from] = 0;
-        self.squares[taken_index] = 0;
+        self.set_sqr(to, moving_piece);
+        self.set_sqr(from, 0);
+        self.set_sqr(taken_index, 0);
+
+        self.hash_key ^= zob_keys::get_piece_key(moving_piece, from) ^ zob_keys::get_piece_key(moving_piece, to)
+            ^ zob_keys::get_piece_key(taken_piece, taken_index);
+
+        taken_piece
     }
 
-    fn undo_enp_mov(&mut self, from: usize, to: usize) {
+    fn undo_enp_mov(&mut self, from: usize, to: usize, taken_piece: u8) {
         let taken_index = if self.player == def::PLAYER_W {
             to - 16
         } else {
@@ -316,33 +444,36 @@ impl State {
         };
 
         let moving_piece = self.squares[to];
-        let taken_piece = self.taken_piece_stack.pop().unwrap();
 
-        self.squares[taken_index] = taken_piece;
-        self.squares[from] = moving_piece;
-        self.squares[to] = 0;
+        self.set_sqr(taken_index, taken_piece);
+        self.set_sqr(from, moving_piece);
+        self.set_sqr(to, 0);
     }
 
-    fn do_cr_enp_mov(&mut self, from: usize, to: usize) {
+    fn do_cr_enp_mov(&mut self, from: usize, to: usize) -> u8 {
         self.enp_square = if self.player == def::PLAYER_W {
             to - 16
         } else {
             to + 16
         };
 
-        self.non_cap_mov_count += 1;
+        self.non_cap_mov_count = 0;
 
         let moving_piece = self.squares[from];
 
-        self.squares[to] = moving_piece;
-        self.squares[from] = 0;
+        self.set_sqr(to, moving_piece);
+        self.set_sqr(from, 0);
+
+        self.hash_key ^= zob_keys::get_piece_key(moving_piece, from) ^ zob_keys::get_piece_key(moving_piece, to);
+
+        0
     }
 
     fn undo_cr_enp_mov(&mut self, from: usize, to: usize) {
         let moving_piece = self.squares[to];
 
-        self.squares[from] = moving_piece;
-        self.squares[to] = 0;
+        self.set_sqr(from, moving_piece);
+        self.set_sqr(to, 0);
     }
 }
 
@@ -378,9 +509,7 @@ fn get_squares_from_fen(fen_squares_string: &str) -> ([u8; def::BOARD_SIZE], usi
     assert_eq!(def::DIM_SIZE, rank_string_list.len());
 
     let mut index = 112;
-    for rank_index in 0..def::DIM_SIZE {
-        let rank_string = rank_string_list[rank_index];
-
+    for rank_string in rank_string_list {
         for char_code in rank_string.chars() {
             if char_code.is_numeric() {
                 index += char_code.to_digit(10).unwrap() as usize;
@@ -413,6 +542,86 @@ fn get_squares_from_fen(fen_squares_string: &str) -> ([u8; def::BOARD_SIZE], usi
     (squares, wk_index, bk_index)
 }
 
+fn get_fen_from_squares(squares: &[u8; def::BOARD_SIZE]) -> String {
+    let mut fen_squares_string = String::new();
+
+    let mut rank_left_index = 112;
+    loop {
+        let mut empty_sqr_count = 0;
+
+        for file_index in 0..def::DIM_SIZE {
+            let piece = squares[rank_left_index + file_index];
+
+            if piece == 0 {
+                empty_sqr_count += 1;
+                continue
+            }
+
+            if empty_sqr_count > 0 {
+                fen_squares_string.push_str(&empty_sqr_count.to_string());
+                empty_sqr_count = 0;
+            }
+
+            fen_squares_string.push(util::map_piece_code_to_fen_char(piece));
+        }
+
+        if empty_sqr_count > 0 {
+            fen_squares_string.push_str(&empty_sqr_count.to_string());
+        }
+
+        if rank_left_index == 0 {
+            break
+        }
+
+        fen_squares_string.push('/');
+        rank_left_index -= 16;
+    }
+
+    fen_squares_string
+}
+
+fn get_fen_from_cas_rights(cas_rights: u8) -> String {
+    if cas_rights == 0 {
+        return "-".to_owned()
+    }
+
+    let mut fen_cas_rights_string = String::new();
+
+    if cas_rights & 0b1000 != 0 {
+        fen_cas_rights_string.push('K');
+    }
+
+    if cas_rights & 0b0100 != 0 {
+        fen_cas_rights_string.push('Q');
+    }
+
+    if cas_rights & 0b0010 != 0 {
+        fen_cas_rights_string.push('k');
+    }
+
+    if cas_rights & 0b0001 != 0 {
+        fen_cas_rights_string.push('q');
+    }
+
+    fen_cas_rights_string
+}
+
+fn gen_hash_key(squares: &[u8; def::BOARD_SIZE], player: u8, cas_rights: u8, enp_sqr: usize) -> u64 {
+    let mut hash_key = 0;
+
+    for (index, piece) in squares.iter().enumerate() {
+        if *piece != 0 {
+            hash_key ^= zob_keys::get_piece_key(*piece, index);
+        }
+    }
+
+    if player == def::PLAYER_B {
+        hash_key ^= zob_keys::get_player_key();
+    }
+
+    hash_key ^ zob_keys::get_cas_rights_key(cas_rights) ^ zob_keys::get_enp_sqr_key(enp_sqr)
+}
+
 fn get_player_from_fen(fen_player_string: &str) -> u8 {
     match fen_player_string {
         "w" => def::PLAYER_W,
@@ -447,6 +656,10 @@ fn get_cas_rights_from_fen(fen_cas_rights_player: &str) -> u8 {
     cas_rights
 }
 
+fn get_mov_count_from_fen(fen_mov_count_string: &str) -> Option<u16> {
+    fen_mov_count_string.parse().ok()
+}
+
 fn get_enp_sqr_from_fen(fen_enp_sqr_string: &str) -> usize {
     if fen_enp_sqr_string == "-" {
         return 0
@@ -455,10 +668,144 @@ fn get_enp_sqr_from_fen(fen_enp_sqr_string: &str) -> usize {
     util::map_sqr_notation_to_index(fen_enp_sqr_string)
 }
 
+pub fn validate_fen(fen_string: &str) -> Result<(), FenError> {
+    let fen_segment_list: Vec<&str> = fen_string.split(" ").collect();
+    if fen_segment_list.len() < FEN_HALF_MOV_INDEX || fen_segment_list.len() > FEN_FULL_MOV_INDEX + 1 {
+        return Err(FenError::Malformed)
+    }
+
+    let mut squares = [0; def::BOARD_SIZE];
+    let rank_string_list: Vec<&str> = fen_segment_list[FEN_SQRS_INDEX].split("/").collect();
+    if rank_string_list.len() != def::DIM_SIZE {
+        return Err(FenError::Malformed)
+    }
+
+    for (rank_offset, rank_string) in rank_string_list.iter().enumerate() {
+        let rank_left_index = (def::DIM_SIZE - 1 - rank_offset) * 16;
+        let mut file_index = 0;
+
+        for char_code in rank_string.chars() {
+            if let Some(empty_sqr_count) = char_code.to_digit(10) {
+                if empty_sqr_count == 0 || empty_sqr_count > 8 {
+                    return Err(FenError::Malformed)
+                }
+
+                file_index += empty_sqr_count as usize;
+            } else if "KQRBNPkqrbnp".contains(char_code) {
+                if file_index >= def::DIM_SIZE {
+                    return Err(FenError::Malformed)
+                }
+
+                squares[rank_left_index + file_index] = util::map_piece_char_to_code(char_code);
+                file_index += 1;
+            } else {
+                return Err(FenError::Malformed)
+            }
+        }
+
+        if file_index != def::DIM_SIZE {
+            return Err(FenError::Malformed)
+        }
+    }
+
+    let player = match fen_segment_list[FEN_PLAYER_INDEX] {
+        "w" => def::PLAYER_W,
+        "b" => def::PLAYER_B,
+        _ => return Err(FenError::Malformed),
+    };
+
+    let fen_cas_rights_string = fen_segment_list[FEN_CAS_RIGHTS_INDEX];
+    if fen_cas_rights_string != "-" && (fen_cas_rights_string.is_empty() || !fen_cas_rights_string.chars().all(|char_code| "KQkq".contains(char_code))) {
+        return Err(FenError::Malformed)
+    }
+
+    let fen_enp_sqr_string = fen_segment_list[FEN_ENP_SQR_INDEX];
+    let enp_sqr = if fen_enp_sqr_string == "-" {
+        0
+    } else {
+        util::parse_sqr_notation(fen_enp_sqr_string).ok_or(FenError::Malformed)?
+    };
+
+    if !fen_segment_list[FEN_HALF_MOV_INDEX..].iter().all(|fen_mov_count_string| get_mov_count_from_fen(fen_mov_count_string).is_some()) {
+        return Err(FenError::Malformed)
+    }
+
+    validate_squares(&squares, player, enp_sqr)
+}
+
+fn validate_squares(squares: &[u8; def::BOARD_SIZE], player: u8, enp_sqr: usize) -> Result<(), FenError> {
+    let mut wk_index_list = Vec::new();
+    let mut bk_index_list = Vec::new();
+    let mut w_piece_count = 0;
+    let mut b_piece_count = 0;
+    let mut w_pawn_count = 0;
+    let mut b_pawn_count = 0;
+
+    for (index, piece) in squares.iter().enumerate() {
+        match *piece {
+            0 => continue,
+            def::WK => wk_index_list.push(index),
+            def::BK => bk_index_list.push(index),
+            def::WP => w_pawn_count += 1,
+            def::BP => b_pawn_count += 1,
+            _ => {},
+        }
+
+        if def::is_p(*piece) && (index / 16 == 0 || index / 16 == def::DIM_SIZE - 1) {
+            return Err(FenError::PawnOnBackRank)
+        }
+
+        if def::on_same_side(def::PLAYER_W, *piece) {
+            w_piece_count += 1;
+        } else {
+            b_piece_count += 1;
+        }
+    }
+
+    if wk_index_list.len() != 1 || bk_index_list.len() != 1 {
+        return Err(FenError::KingCount)
+    }
+
+    if w_piece_count > MAX_PIECE_COUNT || b_piece_count > MAX_PIECE_COUNT {
+        return Err(FenError::TooManyPieces)
+    }
+
+    if w_pawn_count > MAX_PAWN_COUNT || b_pawn_count > MAX_PAWN_COUNT {
+        return Err(FenError::TooManyPawns)
+    }
+
+    if enp_sqr != 0 {
+        let is_enp_sqr_valid = if player == def::PLAYER_W {
+            enp_sqr / 16 == 5 && squares[enp_sqr - 16] == def::BP
+        } else {
+            enp_sqr / 16 == 2 && squares[enp_sqr + 16] == def::WP
+        };
+
+        if !is_enp_sqr_valid || squares[enp_sqr] != 0 {
+            return Err(FenError::InvalidEnpSqr)
+        }
+    }
+
+    let (opponent, opponent_k_index) = if player == def::PLAYER_W {
+        (def::PLAYER_B, bk_index_list[0])
+    } else {
+        (def::PLAYER_W, wk_index_list[0])
+    };
+
+    if MoveGenerator::new().is_sqr_under_attack(squares, opponent, opponent_k_index) {
+        return Err(FenError::OpponentInCheck)
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::def;
+    use crate::{
+        def,
+        mov_gen::MoveGenerator,
+    };
 
     #[test]
     fn test_new_startpos() {
@@ -476,12 +823,12 @@ mod tests {
         assert_eq!(0, state.enp_square);
         assert_eq!(def::PLAYER_W, state.player);
 
-        state.do_mov(util::map_sqr_notation_to_index("e2"), util::map_sqr_notation_to_index("e4"), def::MOV_CR_ENP, 0);
+        let undo_info_e2e4 = state.do_mov(util::map_sqr_notation_to_index("e2"), util::map_sqr_notation_to_index("e4"), def::MOV_CR_ENP, 0);
         assert_eq!(0b1111, state.cas_rights);
         assert_eq!(util::map_sqr_notation_to_index("e3"), state.enp_square);
         assert_eq!(def::PLAYER_B, state.player);
 
-        state.undo_mov(util::map_sqr_notation_to_index("e2"), util::map_sqr_notation_to_index("e4"), def::MOV_CR_ENP);
+        state.undo_mov(util::map_sqr_notation_to_index("e2"), util::map_sqr_notation_to_index("e4"), def::MOV_CR_ENP, undo_info_e2e4);
         assert_eq!(0b1111, state.cas_rights);
         assert_eq!(0, state.enp_square);
         assert_eq!(def::PLAYER_W, state.player);
@@ -495,13 +842,13 @@ mod tests {
         assert_eq!(def::PLAYER_W, state.player);
         assert_eq!(def::BR, state.squares[util::map_sqr_notation_to_index("a8")]);
 
-        state.do_mov(util::map_sqr_notation_to_index("b7"), util::map_sqr_notation_to_index("a8"), def::MOV_PROMO, def::WQ);
+        let undo_info_b7a8 = state.do_mov(util::map_sqr_notation_to_index("b7"), util::map_sqr_notation_to_index("a8"), def::MOV_PROMO, def::WQ);
         assert_eq!(0b1111, state.cas_rights);
         assert_eq!(0, state.enp_square);
         assert_eq!(def::PLAYER_B, state.player);
         assert_eq!(def::WQ, state.squares[util::map_sqr_notation_to_index("a8")]);
 
-        state.undo_mov(util::map_sqr_notation_to_index("b7"), util::map_sqr_notation_to_index("a8"), def::MOV_PROMO);
+        state.undo_mov(util::map_sqr_notation_to_index("b7"), util::map_sqr_notation_to_index("a8"), def::MOV_PROMO, undo_info_b7a8);
         assert_eq!(0b1111, state.cas_rights);
         assert_eq!(0, state.enp_square);
         assert_eq!(def::PLAYER_W, state.player);
@@ -518,7 +865,7 @@ mod tests {
         assert_eq!(def::BR, state.squares[util::map_sqr_notation_to_index("a8")]);
         assert_eq!(0, state.squares[util::map_sqr_notation_to_index("c8")]);
 
-        state.do_mov(util::map_sqr_notation_to_index("e8"), util::map_sqr_notation_to_index("c8"), def::MOV_CAS, 0);
+        let undo_info_e8c8 = state.do_mov(util::map_sqr_notation_to_index("e8"), util::map_sqr_notation_to_index("c8"), def::MOV_CAS, 0);
         assert_eq!(0b0110, state.cas_rights);
         assert_eq!(0, state.enp_square);
         assert_eq!(def::PLAYER_W, state.player);
@@ -527,7 +874,7 @@ mod tests {
         assert_eq!(def::BK, state.squares[util::map_sqr_notation_to_index("c8")]);
         assert_eq!(def::BR, state.squares[util::map_sqr_notation_to_index("d8")]);
 
-        state.undo_mov(util::map_sqr_notation_to_index("e8"), util::map_sqr_notation_to_index("c8"), def::MOV_CAS);
+        state.undo_mov(util::map_sqr_notation_to_index("e8"), util::map_sqr_notation_to_index("c8"), def::MOV_CAS, undo_info_e8c8);
         assert_eq!(0b0111, state.cas_rights);
         assert_eq!(0, state.enp_square);
         assert_eq!(def::PLAYER_B, state.player);
@@ -546,20 +893,20 @@ mod tests {
         assert_eq!(0, state.squares[util::map_sqr_notation_to_index("e2")]);
         assert_eq!(def::BR, state.squares[util::map_sqr_notation_to_index("d2")]);
 
-        state.do_mov(util::map_sqr_notation_to_index("d2"), util::map_sqr_notation_to_index("e2"), def::MOV_REG, 0);
+        let undo_info_d2e2 = state.do_mov(util::map_sqr_notation_to_index("d2"), util::map_sqr_notation_to_index("e2"), def::MOV_REG, 0);
         assert_eq!(0b0000, state.cas_rights);
         assert_eq!(0, state.enp_square);
         assert_eq!(def::PLAYER_W, state.player);
         assert_eq!(0, state.squares[util::map_sqr_notation_to_index("d2")]);
         assert_eq!(def::BR, state.squares[util::map_sqr_notation_to_index("e2")]);
 
-        state.do_mov(util::map_sqr_notation_to_index("d7"), util::map_sqr_notation_to_index("e8"), def::MOV_REG, 0);
-        state.do_mov(util::map_sqr_notation_to_index("e2"), util::map_sqr_notation_to_index("h2"), def::MOV_REG, 0);
+        let undo_info_d7e8 = state.do_mov(util::map_sqr_notation_to_index("d7"), util::map_sqr_notation_to_index("e8"), def::MOV_REG, 0);
+        let undo_info_e2h2 = state.do_mov(util::map_sqr_notation_to_index("e2"), util::map_sqr_notation_to_index("h2"), def::MOV_REG, 0);
 
-        state.undo_mov(util::map_sqr_notation_to_index("e2"), util::map_sqr_notation_to_index("h2"), def::MOV_REG);
-        state.undo_mov(util::map_sqr_notation_to_index("d7"), util::map_sqr_notation_to_index("e8"), def::MOV_REG);
+        state.undo_mov(util::map_sqr_notation_to_index("e2"), util::map_sqr_notation_to_index("h2"), def::MOV_REG, undo_info_e2h2);
+        state.undo_mov(util::map_sqr_notation_to_index("d7"), util::map_sqr_notation_to_index("e8"), def::MOV_REG, undo_info_d7e8);
 
-        state.undo_mov(util::map_sqr_notation_to_index("d2"), util::map_sqr_notation_to_index("e2"), def::MOV_REG);
+        state.undo_mov(util::map_sqr_notation_to_index("d2"), util::map_sqr_notation_to_index("e2"), def::MOV_REG, undo_info_d2e2);
         assert_eq!(0b0000, state.cas_rights);
         assert_eq!(0, state.enp_square);
         assert_eq!(def::PLAYER_B, state.player);
@@ -577,7 +924,7 @@ mod tests {
         assert_eq!(def::BP, state.squares[util::map_sqr_notation_to_index("f5")]);
         assert_eq!(def::WP, state.squares[util::map_sqr_notation_to_index("e5")]);
 
-        state.do_mov(util::map_sqr_notation_to_index("e5"), util::map_sqr_notation_to_index("f6"), def::MOV_ENP, 0);
+        let undo_info_e5f6 = state.do_mov(util::map_sqr_notation_to_index("e5"), util::map_sqr_notation_to_index("f6"), def::MOV_ENP, 0);
         assert_eq!(0b1111, state.cas_rights);
         assert_eq!(0, state.enp_square);
         assert_eq!(def::PLAYER_B, state.player);
@@ -585,7 +932,7 @@ mod tests {
         assert_eq!(0, state.squares[util::map_sqr_notation_to_index("f5")]);
         assert_eq!(0, state.squares[util::map_sqr_notation_to_index("e5")]);
 
-        state.undo_mov(util::map_sqr_notation_to_index("e5"), util::map_sqr_notation_to_index("f6"), def::MOV_ENP);
+        state.undo_mov(util::map_sqr_notation_to_index("e5"), util::map_sqr_notation_to_index("f6"), def::MOV_ENP, undo_info_e5f6);
         assert_eq!(0b1111, state.cas_rights);
         assert_eq!(util::map_sqr_notation_to_index("f6"), state.enp_square);
         assert_eq!(def::PLAYER_W, state.player);
@@ -593,4 +940,305 @@ mod tests {
         assert_eq!(def::BP, state.squares[util::map_sqr_notation_to_index("f5")]);
         assert_eq!(def::WP, state.squares[util::map_sqr_notation_to_index("e5")]);
     }
+
+    #[test]
+    fn test_undo_info() {
+        let mut state = State::new("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 3 5");
+        let hash_key = state.hash_key;
+
+        let undo_info_f3h3 = state.do_mov(util::map_sqr_notation_to_index("f3"), util::map_sqr_notation_to_index("h3"), def::MOV_REG, 0);
+        assert_eq!(UndoInfo { taken_piece: def::BP, cas_rights: 0b1111, enp_square: 0, non_cap_mov_count: 3, hash_key }, undo_info_f3h3);
+
+        let undo_info_e8c8 = state.do_mov(util::map_sqr_notation_to_index("e8"), util::map_sqr_notation_to_index("c8"), def::MOV_CAS, 0);
+        assert_eq!(0, undo_info_e8c8.taken_piece);
+        assert_eq!(util::map_sqr_notation_to_index("c8"), state.bk_index);
+
+        let undo_info_e1d1 = state.do_mov(util::map_sqr_notation_to_index("e1"), util::map_sqr_notation_to_index("d1"), def::MOV_REG, 0);
+        assert_eq!(0b1110, undo_info_e1d1.cas_rights);
+        assert_eq!(util::map_sqr_notation_to_index("d1"), state.wk_index);
+
+        state.undo_mov(util::map_sqr_notation_to_index("e1"), util::map_sqr_notation_to_index("d1"), def::MOV_REG, undo_info_e1d1);
+        assert_eq!(util::map_sqr_notation_to_index("e1"), state.wk_index);
+
+        state.undo_mov(util::map_sqr_notation_to_index("e8"), util::map_sqr_notation_to_index("c8"), def::MOV_CAS, undo_info_e8c8);
+        assert_eq!(util::map_sqr_notation_to_index("e8"), state.bk_index);
+
+        state.undo_mov(util::map_sqr_notation_to_index("f3"), util::map_sqr_notation_to_index("h3"), def::MOV_REG, undo_info_f3h3);
+        assert_eq!(hash_key, state.hash_key);
+        assert_eq!("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 3 5", state.to_fen());
+        assert!(state.hash_key_stack.is_empty());
+    }
+
+    #[test]
+    fn test_hash_key_transposition() {
+        let mut state = State::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let init_hash_key = state.hash_key;
+
+        let undo_info_g1f3 = state.do_mov(util::map_sqr_notation_to_index("g1"), util::map_sqr_notation_to_index("f3"), def::MOV_REG, 0);
+        let undo_info_b8c6 = state.do_mov(util::map_sqr_notation_to_index("b8"), util::map_sqr_notation_to_index("c6"), def::MOV_REG, 0);
+        let undo_info_b1c3 = state.do_mov(util::map_sqr_notation_to_index("b1"), util::map_sqr_notation_to_index("c3"), def::MOV_REG, 0);
+        let hash_key = state.hash_key;
+
+        let mut transposed_state = State::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        transposed_state.do_mov(util::map_sqr_notation_to_index("b1"), util::map_sqr_notation_to_index("c3"), def::MOV_REG, 0);
+        transposed_state.do_mov(util::map_sqr_notation_to_index("b8"), util::map_sqr_notation_to_index("c6"), def::MOV_REG, 0);
+        transposed_state.do_mov(util::map_sqr_notation_to_index("g1"), util::map_sqr_notation_to_index("f3"), def::MOV_REG, 0);
+        assert_eq!(hash_key, transposed_state.hash_key);
+        assert_eq!(hash_key, State::new("r1bqkbnr/pppppppp/2n5/8/8/2N2N2/PPPPPPPP/R1BQKB1R b KQkq - 0 1").hash_key);
+
+        state.undo_mov(util::map_sqr_notation_to_index("b1"), util::map_sqr_notation_to_index("c3"), def::MOV_REG, undo_info_b1c3);
+        state.undo_mov(util::map_sqr_notation_to_index("b8"), util::map_sqr_notation_to_index("c6"), def::MOV_REG, undo_info_b8c6);
+        state.undo_mov(util::map_sqr_notation_to_index("g1"), util::map_sqr_notation_to_index("f3"), def::MOV_REG, undo_info_g1f3);
+        assert_eq!(init_hash_key, state.hash_key);
+    }
+
+    #[test]
+    fn test_hash_key_random_mov_sequence() {
+        let fen_list = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r1bqkbnr/ppp1p1pp/2n5/3pPp2/3P4/8/PPP2PPP/RNBQKBNR w KQkq f6 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        ];
+
+        let mov_generator = MoveGenerator::new();
+        let mut seed: u64 = 0x2545_F491_4F6C_DD1D;
+
+        for fen in fen_list.iter() {
+            let mut state = State::new(fen);
+            let init_hash_key = state.hash_key;
+            let mut mov_stack: Vec<(Option<u32>, UndoInfo)> = Vec::new();
+
+            for _ in 0..500 {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+
+                let (cap_list, non_cap_list) = mov_generator.gen_reg_mov_list(&state, false);
+                let mut mov_list: Vec<u32> = cap_list.iter()
+                    .chain(non_cap_list.iter())
+                    .chain(mov_generator.gen_castle_mov_list(&state).iter())
+                    .copied()
+                    .filter(|mov| {
+                        let (_from, to, _tp, _promo) = util::decode_u32_mov(*mov);
+                        !def::is_k(state.squares[to])
+                    })
+                    .collect();
+
+                if seed.is_multiple_of(5) {
+                    mov_list.clear();
+                }
+
+                if seed.is_multiple_of(7) {
+                    mov_stack.push((None, state.do_null_mov()));
+                } else if !mov_list.is_empty() {
+                    let mov = mov_list[(seed >> 8) as usize % mov_list.len()];
+                    let (from, to, tp, promo) = util::decode_u32_mov(mov);
+                    mov_stack.push((Some(mov), state.do_mov(from, to, tp, promo)));
+                } else {
+                    match mov_stack.pop() {
+                        Some((Some(mov), undo_info)) => {
+                            let (from, to, tp, _promo) = util::decode_u32_mov(mov);
+                            state.undo_mov(from, to, tp, undo_info);
+                        },
+                        Some((None, undo_info)) => state.undo_null_mov(undo_info),
+                        None => (),
+                    }
+                }
+
+                assert_eq!(gen_hash_key(&state.squares, state.player, state.cas_rights, state.enp_square), state.hash_key);
+                assert_eq!(EvalSum::new(&state.squares), state.eval_sum);
+            }
+
+            while let Some((mov, undo_info)) = mov_stack.pop() {
+                match mov {
+                    Some(mov) => {
+                        let (from, to, tp, _promo) = util::decode_u32_mov(mov);
+                        state.undo_mov(from, to, tp, undo_info);
+                    },
+                    None => state.undo_null_mov(undo_info),
+                }
+
+                assert_eq!(gen_hash_key(&state.squares, state.player, state.cas_rights, state.enp_square), state.hash_key);
+            }
+
+            assert_eq!(init_hash_key, state.hash_key);
+        }
+    }
+
+    #[test]
+    fn test_to_fen_round_trip() {
+        let fen_list = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 1",
+            "4k3/8/8/8/8/8/8/4K2R b K - 0 1",
+        ];
+
+        for fen in fen_list.iter() {
+            let state = State::new(fen);
+            assert_eq!(*fen, state.to_fen());
+
+            let round_trip_state = State::new(&state.to_fen());
+            assert_eq!(state.squares, round_trip_state.squares);
+            assert_eq!(state.hash_key, round_trip_state.hash_key);
+        }
+    }
+
+    #[test]
+    fn test_validate_fen() {
+        assert_eq!(Ok(()), validate_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+        assert_eq!(Ok(()), validate_fen("rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 1"));
+        assert_eq!(Ok(()), validate_fen("8/8/8/3K4/8/5k2/5p2/8 b - -"));
+        assert_eq!(Ok(()), validate_fen("7k/8/8/8/8/8/8/K6Q b - - 0 1"));
+
+        assert_eq!(Err(FenError::Malformed), validate_fen(""));
+        assert_eq!(Err(FenError::Malformed), validate_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1"));
+        assert_eq!(Err(FenError::Malformed), validate_fen("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+        assert_eq!(Err(FenError::Malformed), validate_fen("rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+        assert_eq!(Err(FenError::Malformed), validate_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1"));
+        assert_eq!(Err(FenError::Malformed), validate_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1"));
+        assert_eq!(Err(FenError::Malformed), validate_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkx - 0 1"));
+        assert_eq!(Err(FenError::Malformed), validate_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - -1 1"));
+        assert_eq!(Err(FenError::Malformed), validate_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 0"));
+        assert_eq!(Err(FenError::KingCount), validate_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQ1BNR w kq - 0 1"));
+        assert_eq!(Err(FenError::PawnOnBackRank), validate_fen("P3k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+        assert_eq!(Err(FenError::InvalidEnpSqr), validate_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e3 0 1"));
+        assert_eq!(Err(FenError::InvalidEnpSqr), validate_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e6 0 1"));
+        assert_eq!(Err(FenError::TooManyPawns), validate_fen("4k3/pppppppp/p7/8/8/8/8/4K3 w - - 0 1"));
+        assert_eq!(Err(FenError::TooManyPieces), validate_fen("rnbqkbnr/pppppppp/n7/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+        assert_eq!(Err(FenError::OpponentInCheck), validate_fen("7k/8/8/8/8/8/8/K6Q w - - 0 1"));
+        assert_eq!(Err(FenError::OpponentInCheck), validate_fen("4k3/8/8/8/8/8/3p4/4K3 b - - 0 1"));
+    }
+
+    #[test]
+    fn test_to_fen_after_movs() {
+        let mut state = State::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        state.do_mov(util::map_sqr_notation_to_index("g1"), util::map_sqr_notation_to_index("f3"), def::MOV_REG, 0);
+        assert_eq!("rnbqkbnr/pppppppp/8/8/8/5N2/PPPPPPPP/RNBQKB1R b KQkq - 1 1", state.to_fen());
+
+        state.do_mov(util::map_sqr_notation_to_index("b8"), util::map_sqr_notation_to_index("c6"), def::MOV_REG, 0);
+        assert_eq!("r1bqkbnr/pppppppp/2n5/8/8/5N2/PPPPPPPP/RNBQKB1R w KQkq - 2 2", state.to_fen());
+
+        let undo_info_h1g1 = state.do_mov(util::map_sqr_notation_to_index("h1"), util::map_sqr_notation_to_index("g1"), def::MOV_REG, 0);
+        assert_eq!("r1bqkbnr/pppppppp/2n5/8/8/5N2/PPPPPPPP/RNBQKBR1 b Qkq - 3 2", state.to_fen());
+
+        state.undo_mov(util::map_sqr_notation_to_index("h1"), util::map_sqr_notation_to_index("g1"), def::MOV_REG, undo_info_h1g1);
+        assert_eq!("r1bqkbnr/pppppppp/2n5/8/8/5N2/PPPPPPPP/RNBQKB1R w KQkq - 2 2", state.to_fen());
+
+        let mut state = State::new("4k3/8/8/8/8/8/8/4K2R b K - 0 1");
+        state.do_mov(util::map_sqr_notation_to_index("e8"), util::map_sqr_notation_to_index("d8"), def::MOV_REG, 0);
+        assert_eq!("3k4/8/8/8/8/8/8/4K2R w K - 1 2", state.to_fen());
+    }
+
+    #[test]
+    fn test_mov_counts_from_fen() {
+        let state = State::new("8/8/8/3K4/8/5k2/5p2/8 b - - 9 83");
+        assert_eq!(9, state.non_cap_mov_count);
+        assert_eq!(83, state.full_mov_count);
+
+        let state = State::new("8/8/8/3K4/8/5k2/5p2/8 b - -");
+        assert_eq!(0, state.non_cap_mov_count);
+        assert_eq!(1, state.full_mov_count);
+        assert_eq!("8/8/8/3K4/8/5k2/5p2/8 b - - 0 1", state.to_fen());
+    }
+
+    #[test]
+    fn test_mov_counts_after_movs() {
+        let mut state = State::new("r3k2r/p1p2ppp/8/3p4/8/8/PPP2PPP/R3K2R w Kkq - 7 20");
+
+        let undo_info_e1g1 = state.do_mov(util::map_sqr_notation_to_index("e1"), util::map_sqr_notation_to_index("g1"), def::MOV_CAS, 0);
+        assert_eq!(8, state.non_cap_mov_count);
+        assert_eq!(20, state.full_mov_count);
+
+        let undo_info_d5d4 = state.do_mov(util::map_sqr_notation_to_index("d5"), util::map_sqr_notation_to_index("d4"), def::MOV_REG, 0);
+        assert_eq!(0, state.non_cap_mov_count);
+        assert_eq!(21, state.full_mov_count);
+
+        let undo_info_c2c4 = state.do_mov(util::map_sqr_notation_to_index("c2"), util::map_sqr_notation_to_index("c4"), def::MOV_CR_ENP, 0);
+        assert_eq!(0, state.non_cap_mov_count);
+
+        let undo_info_d4c3 = state.do_mov(util::map_sqr_notation_to_index("d4"), util::map_sqr_notation_to_index("c3"), def::MOV_ENP, 0);
+        assert_eq!(0, state.non_cap_mov_count);
+        assert_eq!(22, state.full_mov_count);
+
+        let undo_info_f1e1 = state.do_mov(util::map_sqr_notation_to_index("f1"), util::map_sqr_notation_to_index("e1"), def::MOV_REG, 0);
+        assert_eq!(1, state.non_cap_mov_count);
+        assert_eq!("r3k2r/p1p2ppp/8/8/8/2p5/PP3PPP/R3R1K1 b kq - 1 22", state.to_fen());
+
+        state.undo_mov(util::map_sqr_notation_to_index("f1"), util::map_sqr_notation_to_index("e1"), def::MOV_REG, undo_info_f1e1);
+        state.undo_mov(util::map_sqr_notation_to_index("d4"), util::map_sqr_notation_to_index("c3"), def::MOV_ENP, undo_info_d4c3);
+        state.undo_mov(util::map_sqr_notation_to_index("c2"), util::map_sqr_notation_to_index("c4"), def::MOV_CR_ENP, undo_info_c2c4);
+        state.undo_mov(util::map_sqr_notation_to_index("d5"), util::map_sqr_notation_to_index("d4"), def::MOV_REG, undo_info_d5d4);
+        state.undo_mov(util::map_sqr_notation_to_index("e1"), util::map_sqr_notation_to_index("g1"), def::MOV_CAS, undo_info_e1g1);
+        assert_eq!("r3k2r/p1p2ppp/8/3p4/8/8/PPP2PPP/R3K2R w Kkq - 7 20", state.to_fen());
+    }
+
+    #[test]
+    fn test_fifty_mov_draw_from_fen() {
+        let mut state = State::new("8/8/4k3/8/8/3K4/8/7R w - - 99 120");
+        assert!(!state.is_draw(0));
+
+        let undo_info_h1h2 = state.do_mov(util::map_sqr_notation_to_index("h1"), util::map_sqr_notation_to_index("h2"), def::MOV_REG, 0);
+        assert!(state.is_draw(0));
+
+        state.undo_mov(util::map_sqr_notation_to_index("h1"), util::map_sqr_notation_to_index("h2"), def::MOV_REG, undo_info_h1h2);
+        assert!(!state.is_draw(0));
+    }
+
+    fn do_reg_mov_list(state: &mut State, mov_str_list: &[&str]) {
+        for mov_str in mov_str_list {
+            state.do_mov(util::map_sqr_notation_to_index(&mov_str[0..2]), util::map_sqr_notation_to_index(&mov_str[2..4]), def::MOV_REG, 0);
+        }
+    }
+
+    #[test]
+    fn test_rep_draw() {
+        let mut state = State::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        do_reg_mov_list(&mut state, &["g1f3", "g8f6", "f3g1"]);
+        assert!(!state.is_draw(0));
+        assert!(!state.is_draw(3));
+
+        do_reg_mov_list(&mut state, &["f6g8"]);
+        assert!(!state.is_draw(0));
+        assert!(!state.is_draw(4));
+        assert!(state.is_draw(5));
+
+        do_reg_mov_list(&mut state, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+        assert!(state.is_draw(0));
+    }
+
+    #[test]
+    fn test_rep_draw_transposed() {
+        let mut state = State::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        do_reg_mov_list(&mut state, &["g1f3", "g8f6", "b1c3", "b8c6"]);
+        do_reg_mov_list(&mut state, &["c3b1", "f6g8", "f3g1", "c6b8", "b1c3", "g8f6", "g1f3", "b8c6"]);
+        assert!(!state.is_draw(0));
+        assert!(!state.is_draw(8));
+        assert!(state.is_draw(9));
+
+        do_reg_mov_list(&mut state, &["f3g1", "c6b8", "c3b1", "f6g8", "g1f3", "b8c6", "b1c3", "g8f6"]);
+        assert!(state.is_draw(0));
+    }
+
+    #[test]
+    fn test_rep_draw_bounded_by_null_mov() {
+        let mut state = State::new("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        let hash_key = state.hash_key;
+
+        do_reg_mov_list(&mut state, &["e1d1"]);
+        state.do_null_mov();
+        do_reg_mov_list(&mut state, &["d1e1"]);
+        let undo_info = state.do_null_mov();
+        assert_eq!(hash_key, state.hash_key);
+        assert!(!state.is_draw(5));
+
+        state.undo_null_mov(undo_info);
+        assert_eq!(1, state.non_cap_mov_count);
+    }
 }
@@ -1,155 +1,580 @@
 use crate::{
+    mov_gen::MoveGenerator,
+    options::{self, EngineOption, OptionError},
+    search::SearchLimits,
+    state::{self, FenError, State},
     util,
 };
 
+use std::fmt;
 use std::io::{self, prelude::*};
+use std::str::FromStr;
 
-const DEFAULT_MOVS_TO_GO: u128 = 20;
+const GO_CMD_KEYWORDS: [&str; 13] = [
+    "searchmoves", "ponder", "wtime", "btime", "winc", "binc", "movestogo", "depth", "nodes", "mate", "movetime", "infinite", "perft",
+];
 
-pub struct Rawmov {
-    pub from: usize,
-    pub to: usize,
-    pub promo: String,
-    pub origin_mov_str: String,
-}
+const PROMO_CHAR_LIST: [&str; 4] = ["q", "r", "b", "n"];
 
-pub struct TimeInfo {
-    pub white_millis: u128,
-    pub black_millis: u128,
+pub struct Rawmov {
+    pub(crate) from: usize,
+    pub(crate) to: usize,
+    pub(crate) promo: String,
+    pub(crate) origin_mov_str: String,
 }
 
 pub enum UciProcessResult {
     Noop,
     Ready,
     Reset,
-    Position(Vec<Rawmov>),
-    StartSearchWithTime(u128),
-    StartSearchWithComplextTimeControl(TimeInfo),
+    Position(Option<String>, Vec<Rawmov>),
+    SetOption(EngineOption),
+    StartSearch(Box<SearchLimits>, Vec<Rawmov>),
+    PonderHit,
+    Perft(u8),
+    Display,
     Stop,
     Quit,
 }
 
+#[derive(Debug, PartialEq)]
+pub enum UciError {
+    UnknownCommand(String),
+    UnknownParam(String),
+    MissingValue(String),
+    InvalidValue(String, String),
+    UnknownPositionType(String),
+    MissingPositionType,
+    InvalidFen(String, FenError),
+    InvalidMove(String),
+    IllegalMove(String),
+    AmbiguousMove(String),
+    MissingOptionName,
+    Option(OptionError),
+}
+
+impl fmt::Display for UciError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UciError::UnknownCommand(cmd) => write!(f, "unknown command {}", cmd),
+            UciError::UnknownParam(param) => write!(f, "unknown parameter {}", param),
+            UciError::MissingValue(param) => write!(f, "missing value for {}", param),
+            UciError::InvalidValue(param, value) => write!(f, "invalid value {} for {}", value, param),
+            UciError::UnknownPositionType(pos_type) => write!(f, "unknown position type {}", pos_type),
+            UciError::MissingPositionType => write!(f, "position requires startpos or fen"),
+            UciError::InvalidFen(fen, error) => write!(f, "invalid fen {}: {}", fen, error),
+            UciError::InvalidMove(mov_str) => write!(f, "invalid move {}", mov_str),
+            UciError::IllegalMove(mov_str) => write!(f, "illegal move {}", mov_str),
+            UciError::AmbiguousMove(mov_str) => write!(f, "ambiguous move {}", mov_str),
+            UciError::MissingOptionName => write!(f, "setoption requires a name"),
+            UciError::Option(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<OptionError> for UciError {
+    fn from(error: OptionError) -> UciError {
+        UciError::Option(error)
+    }
+}
+
 pub fn process_uci_cmd(uci_cmd: &str) -> UciProcessResult {
-    let mut cmd_seq: Vec<&str> = uci_cmd.split(' ').collect();
-    match cmd_seq[0] {
+    match parse_uci_cmd(uci_cmd) {
+        Ok(uci_process_result) => uci_process_result,
+        Err(error) => {
+            println!("info string {}", error);
+            io::stdout().flush().ok();
+            UciProcessResult::Noop
+        },
+    }
+}
+
+pub fn parse_uci_cmd(uci_cmd: &str) -> Result<UciProcessResult, UciError> {
+    let token_list: Vec<&str> = uci_cmd.split_whitespace().collect();
+
+    let (cmd, param_list) = match token_list.split_first() {
+        Some((cmd, param_list)) => (*cmd, param_list),
+        None => return Ok(UciProcessResult::Noop),
+    };
+
+    match cmd {
         "uci" => {
             println!("id name FoxSEE 0.1.8");
             println!("id author Zixiao Han");
+            for option_decl in options::OPTION_DECL_LIST.iter() {
+                println!("{}", option_decl);
+            }
             println!("uciok");
             io::stdout().flush().ok();
-            UciProcessResult::Ready
+            Ok(UciProcessResult::Ready)
         }
-        "debug" => UciProcessResult::Noop,
+        "debug" => Ok(UciProcessResult::Noop),
         "isready" => {
             println!("readyok");
             io::stdout().flush().ok();
-            UciProcessResult::Noop
+            Ok(UciProcessResult::Noop)
         }
-        "setoption" => UciProcessResult::Noop,
-        "register" => UciProcessResult::Noop,
-        "ucinewgame" => UciProcessResult::Reset,
-        "position" => match cmd_seq[1] {
-            "startpos" => {
-                if cmd_seq.len() > 3 {
-                    process_position_startpos_with_mov_list(cmd_seq.split_off(3))
-                } else {
-                    process_position_startpos()
+        "setoption" => parse_setoption_cmd(param_list),
+        "register" => Ok(UciProcessResult::Noop),
+        "ucinewgame" => Ok(UciProcessResult::Reset),
+        "position" => parse_position_cmd(param_list),
+        "go" => parse_go_cmd(param_list),
+        "stop" => Ok(UciProcessResult::Stop),
+        "ponderhit" => Ok(UciProcessResult::PonderHit),
+        "quit" => Ok(UciProcessResult::Quit),
+        "d" => Ok(UciProcessResult::Display),
+        _ => Err(UciError::UnknownCommand(cmd.to_owned())),
+    }
+}
+
+fn parse_setoption_cmd(param_list: &[&str]) -> Result<UciProcessResult, UciError> {
+    if param_list.first() != Some(&"name") || param_list.len() < 2 {
+        return Err(UciError::MissingOptionName)
+    }
+
+    let (name, value) = match param_list.iter().position(|param| *param == "value") {
+        Some(value_index) => (param_list[1..value_index].join(" "), Some(param_list[value_index + 1..].join(" "))),
+        None => (param_list[1..].join(" "), None),
+    };
+
+    Ok(UciProcessResult::SetOption(options::parse_engine_option(&name, value.as_deref())?))
+}
+
+fn parse_go_cmd(param_list: &[&str]) -> Result<UciProcessResult, UciError> {
+    let mut search_limits = SearchLimits::default();
+    let mut searchmov_list = Vec::new();
+
+    let mut index = 0;
+    while index < param_list.len() {
+        match param_list[index] {
+            "ponder" => search_limits.ponder = true,
+            "perft" => return Ok(UciProcessResult::Perft(parse_go_param(param_list, index)?)),
+            "infinite" => search_limits.infinite = true,
+            "searchmoves" => {
+                while index + 1 < param_list.len() && !GO_CMD_KEYWORDS.contains(&param_list[index + 1]) {
+                    searchmov_list.push(parse_mov_str(param_list[index + 1])?);
+                    index += 1;
                 }
-            }
-            _ => {
-                eprintln!("only support startpos with mov list");
-                UciProcessResult::Noop
             },
-        },
-        "go" => process_go_cmd(cmd_seq.split_off(0)),
-        "stop" => UciProcessResult::Stop,
-        "ponderhit" => UciProcessResult::Noop,
-        "quit" => UciProcessResult::Quit,
-        _ => {
-            eprintln!("unknown uci command {}", cmd_seq[0]);
-            UciProcessResult::Noop
+            "wtime" => {
+                search_limits.wtime = Some(parse_time_param(param_list, index)?);
+                index += 1;
+            },
+            "btime" => {
+                search_limits.btime = Some(parse_time_param(param_list, index)?);
+                index += 1;
+            },
+            "winc" => {
+                search_limits.winc = Some(parse_time_param(param_list, index)?);
+                index += 1;
+            },
+            "binc" => {
+                search_limits.binc = Some(parse_time_param(param_list, index)?);
+                index += 1;
+            },
+            "movetime" => {
+                search_limits.movetime = Some(parse_time_param(param_list, index)?);
+                index += 1;
+            },
+            "movestogo" => {
+                search_limits.movestogo = Some(parse_go_param(param_list, index)?);
+                index += 1;
+            },
+            "depth" => {
+                search_limits.depth = Some(parse_go_param(param_list, index)?);
+                index += 1;
+            },
+            "nodes" => {
+                search_limits.nodes = Some(parse_go_param(param_list, index)?);
+                index += 1;
+            },
+            "mate" => {
+                search_limits.mate = Some(parse_go_param(param_list, index)?);
+                index += 1;
+            },
+            param => return Err(UciError::UnknownParam(param.to_owned())),
         }
+
+        index += 1;
     }
+
+    Ok(UciProcessResult::StartSearch(Box::new(search_limits), searchmov_list))
 }
 
-fn process_go_cmd(go_cmd_seq: Vec<&str>) -> UciProcessResult {
-    match go_cmd_seq[1] {
-        "ponder" => UciProcessResult::Noop,
-        "movetime" => UciProcessResult::StartSearchWithTime(go_cmd_seq[2].parse::<u128>().unwrap()),
-        "wtime" => process_time_control(go_cmd_seq),
-        sub_cmd => panic!("unsupported sub command {}", sub_cmd),
-    }
+fn parse_go_param<T: FromStr>(param_list: &[&str], index: usize) -> Result<T, UciError> {
+    let param = param_list[index];
+    let value = param_list.get(index + 1).ok_or_else(|| UciError::MissingValue(param.to_owned()))?;
+
+    value.parse::<T>().map_err(|_| UciError::InvalidValue(param.to_owned(), (*value).to_owned()))
 }
 
-fn process_time_control(go_cmd_seq: Vec<&str>) -> UciProcessResult {
-    assert!(go_cmd_seq[1] == "wtime");
-    let wtime = go_cmd_seq[2].parse::<u128>().unwrap();
-        
-    assert!(go_cmd_seq[3] == "btime");
-    let btime = go_cmd_seq[4].parse::<u128>().unwrap();
+fn parse_time_param(param_list: &[&str], index: usize) -> Result<u128, UciError> {
+    parse_go_param::<i128>(param_list, index).map(|time_millis| time_millis.max(0) as u128)
+}
 
-    let movs_to_go = if go_cmd_seq[5] == "movestogo" {
-        go_cmd_seq[6].parse::<u128>().unwrap()
-    } else if go_cmd_seq[9] == "movestogo" {
-        go_cmd_seq[10].parse::<u128>().unwrap()
-    } else {
-        eprintln!("no movestogo specified, use default");
-        DEFAULT_MOVS_TO_GO
+fn parse_position_cmd(param_list: &[&str]) -> Result<UciProcessResult, UciError> {
+    let (position_param_list, mov_str_list) = match param_list.iter().position(|param| *param == "moves") {
+        Some(moves_index) => (&param_list[..moves_index], &param_list[moves_index + 1..]),
+        None => (param_list, &[][..]),
     };
 
-    UciProcessResult::StartSearchWithComplextTimeControl(TimeInfo{
-        white_millis: wtime / movs_to_go,
-        black_millis: btime / movs_to_go,
-    })
-}
+    let fen = match position_param_list.split_first() {
+        Some((&"startpos", [])) => None,
+        Some((&"startpos", [param, ..])) => return Err(UciError::UnknownParam((*param).to_owned())),
+        Some((&"fen", fen_param_list)) => {
+            let fen = fen_param_list.join(" ");
+            if let Err(error) = state::validate_fen(&fen) {
+                return Err(UciError::InvalidFen(fen, error))
+            }
 
-fn process_position_startpos() -> UciProcessResult {
-    UciProcessResult::Position(vec![])
+            Some(fen)
+        },
+        Some((pos_type, _)) => return Err(UciError::UnknownPositionType((*pos_type).to_owned())),
+        None => return Err(UciError::MissingPositionType),
+    };
+
+    let mov_list = mov_str_list.iter()
+        .map(|mov_str| parse_mov_str(mov_str))
+        .collect::<Result<Vec<Rawmov>, UciError>>()?;
+
+    Ok(UciProcessResult::Position(fen, mov_list))
 }
 
-fn process_position_startpos_with_mov_list(mov_str_list: Vec<&str>) -> UciProcessResult {
-    let mut mov_list = Vec::new();
-    for mov_str in mov_str_list {
-        mov_list.push(parse_mov_str(mov_str));
+pub fn find_legal_mov(state: &State, mov_generator: &MoveGenerator, raw_mov: &Rawmov) -> Result<u32, UciError> {
+    let legal_mov_list = mov_generator.gen_legal_mov_list(state);
+    let mut matched_mov_list = legal_mov_list.iter().filter(|mov| {
+        let (from, to, _tp, promo) = util::decode_u32_mov(**mov);
+        let promo_char = if promo == 0 { None } else { Some(util::map_promo_piece_to_char(promo)) };
+
+        from == raw_mov.from && to == raw_mov.to && promo_char == raw_mov.promo.chars().next()
+    });
+
+    match (matched_mov_list.next(), matched_mov_list.next()) {
+        (Some(mov), None) => Ok(*mov),
+        (Some(_), Some(_)) => Err(UciError::AmbiguousMove(raw_mov.origin_mov_str.clone())),
+        (None, _) => Err(UciError::IllegalMove(raw_mov.origin_mov_str.clone())),
     }
+}
 
-    UciProcessResult::Position(mov_list)
+pub fn resolve_searchmoves(state: &State, mov_generator: &MoveGenerator, raw_mov_list: &[Rawmov]) -> Result<Vec<u32>, UciError> {
+    raw_mov_list.iter()
+        .map(|raw_mov| find_legal_mov(state, mov_generator, raw_mov).map_err(|_| UciError::InvalidMove(raw_mov.origin_mov_str.clone())))
+        .collect()
 }
 
-fn parse_mov_str(mov_str: &str) -> Rawmov {
-    let from_str = &mov_str[0..2];
-    let to_str = &mov_str[2..4];
+fn parse_mov_str(mov_str: &str) -> Result<Rawmov, UciError> {
+    if !mov_str.is_ascii() || (mov_str.len() != 4 && mov_str.len() != 5) {
+        return Err(UciError::InvalidMove(mov_str.to_owned()))
+    }
 
-    let mut promotion_piece = String::new();
-    if mov_str.len() == 5 {
-        promotion_piece.push_str(&mov_str[4..]);
+    let from = util::parse_sqr_notation(&mov_str[0..2]).ok_or_else(|| UciError::InvalidMove(mov_str.to_owned()))?;
+    let to = util::parse_sqr_notation(&mov_str[2..4]).ok_or_else(|| UciError::InvalidMove(mov_str.to_owned()))?;
+
+    let promo = &mov_str[4..];
+    if !promo.is_empty() && !PROMO_CHAR_LIST.contains(&promo) {
+        return Err(UciError::InvalidMove(mov_str.to_owned()))
     }
 
-    Rawmov {
-        from: util::map_sqr_notation_to_index(from_str),
-        to: util::map_sqr_notation_to_index(to_str),
-        promo: promotion_piece,
+    Ok(Rawmov {
+        from,
+        to,
+        promo: promo.to_owned(),
         origin_mov_str: mov_str.to_owned(),
-    }
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{
-        util,
-    };
+    use crate::def;
 
     #[test]
     fn test_parse_mov_str() {
-        let raw_mov = parse_mov_str("e1g1");
+        let raw_mov = parse_mov_str("e1g1").unwrap();
         assert_eq!(util::map_sqr_notation_to_index("e1"), raw_mov.from);
         assert_eq!(util::map_sqr_notation_to_index("g1"), raw_mov.to);
         assert_eq!("".to_owned(), raw_mov.promo);
 
-        let raw_mov = parse_mov_str("a7b8q");
+        let raw_mov = parse_mov_str("a7b8q").unwrap();
         assert_eq!(util::map_sqr_notation_to_index("a7"), raw_mov.from);
         assert_eq!(util::map_sqr_notation_to_index("b8"), raw_mov.to);
         assert_eq!("q".to_owned(), raw_mov.promo);
+
+        for invalid_mov_str in ["", "e2", "e2e", "i2e4", "e2e9", "e7e8k", "e2e4qq", "é2e4"].iter() {
+            assert_eq!(Some(UciError::InvalidMove(invalid_mov_str.to_string())), parse_mov_str(invalid_mov_str).err());
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_find_legal_mov() {
+        let mov_generator = MoveGenerator::new();
+        let state = State::new("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq a3 0 1");
+
+        for (mov_str, expected_tp) in [("e8g8", def::MOV_CAS), ("e8c8", def::MOV_CAS), ("b4a3", def::MOV_ENP), ("c7c5", def::MOV_CR_ENP), ("h3g2", def::MOV_REG)].iter() {
+            let mov = find_legal_mov(&state, &mov_generator, &parse_mov_str(mov_str).unwrap()).unwrap();
+            let (_from, _to, tp, _promo) = util::decode_u32_mov(mov);
+            assert_eq!(*expected_tp, tp);
+        }
+
+        for mov_str in ["e2e4", "a1a2", "e8e7", "g7h8"].iter() {
+            assert_eq!(Err(UciError::IllegalMove(mov_str.to_string())), find_legal_mov(&state, &mov_generator, &parse_mov_str(mov_str).unwrap()));
+        }
+
+        let state = State::new("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+
+        let mov = find_legal_mov(&state, &mov_generator, &parse_mov_str("b7b8n").unwrap()).unwrap();
+        assert_eq!("b7b8n", util::format_mov(mov));
+        assert_eq!(Err(UciError::IllegalMove("b7b8".to_owned())), find_legal_mov(&state, &mov_generator, &parse_mov_str("b7b8").unwrap()));
+    }
+
+    #[test]
+    fn test_resolve_searchmoves() {
+        let mov_generator = MoveGenerator::new();
+        let state = State::new("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq a3 0 1");
+
+        let searchmov_list = ["e8g8", "b4a3"].iter().map(|mov_str| parse_mov_str(mov_str).unwrap()).collect::<Vec<Rawmov>>();
+        let searchmoves = resolve_searchmoves(&state, &mov_generator, &searchmov_list).unwrap();
+        assert_eq!(vec!["e8g8", "b4a3"], searchmoves.iter().map(|mov| util::format_mov(*mov)).collect::<Vec<String>>());
+
+        let searchmov_list = ["e8g8", "e2e4"].iter().map(|mov_str| parse_mov_str(mov_str).unwrap()).collect::<Vec<Rawmov>>();
+        assert_eq!(Err(UciError::InvalidMove("e2e4".to_owned())), resolve_searchmoves(&state, &mov_generator, &searchmov_list));
+
+        assert_eq!(Ok(Vec::new()), resolve_searchmoves(&state, &mov_generator, &[]));
+    }
+
+    #[test]
+    fn test_parse_uci_cmd_errors() {
+        assert!(matches!(parse_uci_cmd(""), Ok(UciProcessResult::Noop)));
+        assert!(matches!(parse_uci_cmd("  \t "), Ok(UciProcessResult::Noop)));
+        assert!(matches!(parse_uci_cmd("  stop  "), Ok(UciProcessResult::Stop)));
+
+        assert_eq!(Some(UciError::UnknownCommand("hello".to_owned())), parse_uci_cmd("hello world").err());
+        assert_eq!(Some(UciError::MissingPositionType), parse_uci_cmd("position").err());
+        assert_eq!(Some(UciError::UnknownPositionType("kiwipete".to_owned())), parse_uci_cmd("position kiwipete").err());
+        assert_eq!(Some(UciError::UnknownParam("e2e4".to_owned())), parse_uci_cmd("position startpos e2e4").err());
+        assert_eq!(Some(UciError::InvalidFen("8/8/8 w - -".to_owned(), FenError::Malformed)), parse_uci_cmd("position fen 8/8/8 w - -").err());
+        assert_eq!(Some(UciError::InvalidFen("7k/8/8/8/8/8/8/K6Q w - - 0 1".to_owned(), FenError::OpponentInCheck)), parse_uci_cmd("position fen 7k/8/8/8/8/8/8/K6Q w - - 0 1").err());
+        assert_eq!(Some(UciError::InvalidFen("4k3/pppppppp/p7/8/8/8/8/4K3 b - - 0 1".to_owned(), FenError::TooManyPawns)), parse_uci_cmd("position fen 4k3/pppppppp/p7/8/8/8/8/4K3 b - - 0 1").err());
+        assert_eq!(Some(UciError::InvalidMove("e2".to_owned())), parse_uci_cmd("position startpos moves e2e4 e2").err());
+        assert_eq!(Some(UciError::UnknownParam("fast".to_owned())), parse_uci_cmd("go fast").err());
+        assert_eq!(Some(UciError::MissingValue("wtime".to_owned())), parse_uci_cmd("go wtime").err());
+        assert_eq!(Some(UciError::InvalidValue("depth".to_owned(), "300".to_owned())), parse_uci_cmd("go depth 300").err());
+        assert_eq!(Some(UciError::InvalidMove("e2e9".to_owned())), parse_uci_cmd("go searchmoves e2e4 e2e9").err());
+        assert_eq!(Some(UciError::MissingOptionName), parse_uci_cmd("setoption name").err());
+        assert_eq!(Some(UciError::Option(OptionError::UnknownOption("Style".to_owned()))), parse_uci_cmd("setoption name Style value Solid").err());
+    }
+
+    #[test]
+    fn test_process_uci_cmd_random_input() {
+        let token_list = [
+            "uci", "isready", "ucinewgame", "position", "startpos", "fen", "moves", "go", "setoption", "name", "value", "Hash", "Move", "Overhead", "MultiPV",
+            "Clear", "ponder", "ponderhit", "wtime", "btime", "winc", "binc", "movestogo", "depth", "nodes", "mate", "movetime", "infinite", "searchmoves",
+            "perft", "stop", "d", "debug", "register", "e2e4", "a7a8q", "e7e8k", "z9z9", "e2", "-1", "0", "3", "300", "99999999999999999999999",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR", "8/8/8/3K4/8/5k2/5p2/8", "8/8/8/8/8/8/8/8", "P3k3/8/8/8/8/8/8/4K3", "7k/8/8/8/8/8/8/K6Q", "w", "b", "KQkq", "-", "e3", "c6",
+            "é", "ü4e5", "true", "<empty>",
+        ];
+        let prefix_list = ["", "position startpos moves", "position fen", "position fen 8/8/8/3K4/8/5k2/5p2/8", "position fen 7k/8/8/8/8/8/8/K6Q", "go", "setoption name"];
+        let separator_list = [" ", "  ", "\t"];
+        let mov_generator = MoveGenerator::new();
+
+        let mut seed: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut next_rand = move |bound: usize| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % bound as u64) as usize
+        };
+
+        for _ in 0..20000 {
+            let mut uci_cmd = prefix_list[next_rand(prefix_list.len())].to_owned();
+
+            for _ in 0..next_rand(12) {
+                uci_cmd.push_str(separator_list[next_rand(separator_list.len())]);
+
+                if next_rand(8) == 0 {
+                    uci_cmd.extend((0..next_rand(6)).map(|_| std::char::from_u32(next_rand(0x250) as u32).unwrap_or('?')));
+                } else {
+                    uci_cmd.push_str(token_list[next_rand(token_list.len())]);
+                }
+            }
+
+            if let UciProcessResult::Position(Some(fen), mov_list) = process_uci_cmd(uci_cmd.trim()) {
+                let state = State::new(&fen);
+                let (opponent, opponent_k_index) = if state.player == def::PLAYER_W {
+                    (def::PLAYER_B, state.bk_index)
+                } else {
+                    (def::PLAYER_W, state.wk_index)
+                };
+
+                assert!(!mov_generator.is_sqr_under_attack(&state.squares, opponent, opponent_k_index), "{} accepted with the side not to move in check", fen);
+                assert!(mov_list.iter().all(|raw_mov| raw_mov.from < 128 && raw_mov.to < 128));
+            }
+        }
+    }
+
+    #[test]
+    fn test_process_position_startpos() {
+        match process_uci_cmd("position startpos") {
+            UciProcessResult::Position(fen, mov_list) => {
+                assert_eq!(None, fen);
+                assert!(mov_list.is_empty());
+            },
+            _ => panic!("position not processed"),
+        }
+
+        match process_uci_cmd("position startpos moves e2e4 e7e5 g1f3") {
+            UciProcessResult::Position(fen, mov_list) => {
+                assert_eq!(None, fen);
+                assert_eq!(3, mov_list.len());
+                assert_eq!("g1f3".to_owned(), mov_list[2].origin_mov_str);
+            },
+            _ => panic!("position not processed"),
+        }
+    }
+
+    #[test]
+    fn test_process_position_fen() {
+        match process_uci_cmd("position fen 8/8/8/3K4/8/5k2/5p2/8 b - - 9 83") {
+            UciProcessResult::Position(fen, mov_list) => {
+                assert_eq!(Some("8/8/8/3K4/8/5k2/5p2/8 b - - 9 83".to_owned()), fen);
+                assert!(mov_list.is_empty());
+            },
+            _ => panic!("position not processed"),
+        }
+
+        match process_uci_cmd("position fen 8/8/8/3K4/8/5k2/5p2/8 b - - 9 83 moves f2f1q d5e4") {
+            UciProcessResult::Position(fen, mov_list) => {
+                assert_eq!(Some("8/8/8/3K4/8/5k2/5p2/8 b - - 9 83".to_owned()), fen);
+                assert_eq!(2, mov_list.len());
+                assert_eq!("q".to_owned(), mov_list[0].promo);
+                assert_eq!(util::map_sqr_notation_to_index("e4"), mov_list[1].to);
+            },
+            _ => panic!("position not processed"),
+        }
+    }
+
+    #[test]
+    fn test_process_go_cmd_time_control() {
+        match process_uci_cmd("go wtime 60000 btime 30000 winc 1000 binc 500") {
+            UciProcessResult::StartSearch(search_limits, _) => {
+                assert_eq!(Some(60000), search_limits.wtime);
+                assert_eq!(Some(30000), search_limits.btime);
+                assert_eq!(Some(1000), search_limits.winc);
+                assert_eq!(Some(500), search_limits.binc);
+                assert_eq!(None, search_limits.movestogo);
+                assert_eq!(None, search_limits.movetime);
+            },
+            _ => panic!("go not processed"),
+        }
+
+        match process_uci_cmd("go movestogo 12 btime 3000 wtime -200") {
+            UciProcessResult::StartSearch(search_limits, _) => {
+                assert_eq!(Some(0), search_limits.wtime);
+                assert_eq!(Some(3000), search_limits.btime);
+                assert_eq!(Some(12), search_limits.movestogo);
+                assert_eq!(None, search_limits.winc);
+            },
+            _ => panic!("go not processed"),
+        }
+
+        match process_uci_cmd("go movetime 5000") {
+            UciProcessResult::StartSearch(search_limits, _) => {
+                assert_eq!(Some(5000), search_limits.movetime);
+                assert!(!search_limits.infinite);
+            },
+            _ => panic!("go not processed"),
+        }
+    }
+
+    #[test]
+    fn test_process_go_cmd_limits() {
+        match process_uci_cmd("go depth 8 nodes 100000 mate 3 searchmoves a2a3") {
+            UciProcessResult::StartSearch(search_limits, searchmov_list) => {
+                assert_eq!(Some(8), search_limits.depth);
+                assert_eq!(Some(100000), search_limits.nodes);
+                assert_eq!(Some(3), search_limits.mate);
+                assert_eq!(vec!["a2a3"], searchmov_list.iter().map(|raw_mov| raw_mov.origin_mov_str.as_str()).collect::<Vec<&str>>());
+            },
+            _ => panic!("go not processed"),
+        }
+
+        match process_uci_cmd("go searchmoves e2e4 d2d4 infinite") {
+            UciProcessResult::StartSearch(search_limits, searchmov_list) => {
+                assert!(search_limits.infinite);
+                assert_eq!(vec!["e2e4", "d2d4"], searchmov_list.iter().map(|raw_mov| raw_mov.origin_mov_str.as_str()).collect::<Vec<&str>>());
+            },
+            _ => panic!("go not processed"),
+        }
+
+        match process_uci_cmd("go") {
+            UciProcessResult::StartSearch(search_limits, searchmov_list) => {
+                assert!(!search_limits.infinite);
+                assert!(searchmov_list.is_empty());
+                assert_eq!(None, search_limits.depth);
+            },
+            _ => panic!("go not processed"),
+        }
+    }
+
+    #[test]
+    fn test_process_go_cmd_ponder() {
+        match process_uci_cmd("go ponder wtime 1000 btime 1000") {
+            UciProcessResult::StartSearch(search_limits, _) => {
+                assert!(search_limits.ponder);
+                assert_eq!(Some(1000), search_limits.wtime);
+            },
+            _ => panic!("go ponder not processed"),
+        }
+
+        match process_uci_cmd("ponderhit") {
+            UciProcessResult::PonderHit => {},
+            _ => panic!("ponderhit not processed"),
+        }
+    }
+
+    #[test]
+    fn test_process_go_cmd_perft() {
+        match process_uci_cmd("go perft 5") {
+            UciProcessResult::Perft(depth) => assert_eq!(5, depth),
+            _ => panic!("go perft not processed"),
+        }
+
+        match process_uci_cmd("go perft") {
+            UciProcessResult::Noop => {},
+            _ => panic!("go perft without depth not ignored"),
+        }
+    }
+
+    #[test]
+    fn test_process_setoption_cmd() {
+        match process_uci_cmd("setoption name Hash value 256") {
+            UciProcessResult::SetOption(engine_option) => assert_eq!(EngineOption::Hash(256), engine_option),
+            _ => panic!("setoption not processed"),
+        }
+
+        match process_uci_cmd("setoption name Move Overhead value 120") {
+            UciProcessResult::SetOption(engine_option) => assert_eq!(EngineOption::MoveOverhead(120), engine_option),
+            _ => panic!("setoption not processed"),
+        }
+
+        match process_uci_cmd("setoption name Clear Hash") {
+            UciProcessResult::SetOption(engine_option) => assert_eq!(EngineOption::ClearHash, engine_option),
+            _ => panic!("setoption not processed"),
+        }
+
+        for invalid_cmd in ["setoption name Hash value big", "setoption name Ponder", "setoption name Style value Solid", "setoption Hash 32"].iter() {
+            match process_uci_cmd(invalid_cmd) {
+                UciProcessResult::Noop => {},
+                _ => panic!("invalid setoption not ignored: {}", invalid_cmd),
+            }
+        }
+    }
+
+    #[test]
+    fn test_process_display_cmd() {
+        match process_uci_cmd("d") {
+            UciProcessResult::Display => {},
+            _ => panic!("d not processed"),
+        }
+    }
+}
@@ -22,6 +22,26 @@ pub fn map_piece_char_to_code(piece_char: char) -> u8 {
     }
 }
 
+pub fn map_piece_code_to_fen_char(piece_code: u8) -> char {
+    match piece_code {
+        def::WK => 'K',
+        def::WQ => 'Q',
+        def::WR => 'R',
+        def::WB => 'B',
+        def::WN => 'N',
+        def::WP => 'P',
+
+        def::BK => 'k',
+        def::BQ => 'q',
+        def::BR => 'r',
+        def::BB => 'b',
+        def::BN => 'n',
+        def::BP => 'p',
+
+        _ => panic!("invalid piece code {}", piece_code),
+    }
+}
+
 pub fn map_piece_code_to_char(piece_code: u8) -> char {
     match piece_code {
         def::WK => '♔',
@@ -77,8 +97,15 @@ pub fn map_index_to_sqr_notation(index: usize) -> String {
 }
 
 pub fn map_sqr_notation_to_index(sqr_notation: &str) -> usize {
-    let sqr_notation_chars: Vec<char> = sqr_notation.chars().collect();
-    let file_index = match sqr_notation_chars[0] {
+    match parse_sqr_notation(sqr_notation) {
+        Some(index) => index,
+        None => panic!("invalid square {}", sqr_notation),
+    }
+}
+
+pub fn parse_sqr_notation(sqr_notation: &str) -> Option<usize> {
+    let mut sqr_notation_chars = sqr_notation.chars();
+    let file_index = match sqr_notation_chars.next()? {
         'a' => 0,
         'b' => 1,
         'c' => 2,
@@ -87,12 +114,19 @@ pub fn map_sqr_notation_to_index(sqr_notation: &str) -> usize {
         'f' => 5,
         'g' => 6,
         'h' => 7,
-        r => panic!("invalid file {}", r),
+        _ => return None,
     };
 
-    let rank_index = sqr_notation_chars[1].to_digit(10).unwrap() - 1;
+    let rank_index = match sqr_notation_chars.next()?.to_digit(10)? {
+        rank @ 1..=8 => rank - 1,
+        _ => return None,
+    };
 
-    (rank_index * 16 + file_index) as usize
+    if sqr_notation_chars.next().is_some() {
+        return None
+    }
+
+    Some((rank_index * 16 + file_index) as usize)
 }
 
 pub fn encode_u32_mov(from: usize, to: usize, tp: u8, promo: u8) -> u32 {
@@ -126,7 +160,7 @@ pub fn format_pv(pv_table: &[u32]) -> String {
             break
         }
 
-        pv_line.push_str(&&*format!("{} ", format_mov(*mov)));
+        pv_line.push_str(&format!("{} ", format_mov(*mov)));
     }
 
     pv_line
@@ -149,6 +183,18 @@ mod tests {
         assert_eq!(112, map_sqr_notation_to_index("a8"));
     }
 
+    #[test]
+    fn test_parse_sqr_notation() {
+        assert_eq!(Some(49), parse_sqr_notation("b4"));
+        assert_eq!(None, parse_sqr_notation(""));
+        assert_eq!(None, parse_sqr_notation("e"));
+        assert_eq!(None, parse_sqr_notation("i4"));
+        assert_eq!(None, parse_sqr_notation("a0"));
+        assert_eq!(None, parse_sqr_notation("a9"));
+        assert_eq!(None, parse_sqr_notation("e4e"));
+        assert_eq!(None, parse_sqr_notation("é4"));
+    }
+
     #[test]
     fn test_encode_decode_mov_1() {
         let encoded = encode_u32_mov(util::map_sqr_notation_to_index("e6"), util::map_sqr_notation_to_index("a8"), def::MOV_REG, 0);
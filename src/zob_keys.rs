@@ -0,0 +1,138 @@
+use crate::def;
+
+const PIECE_TYPE_COUNT: usize = 12;
+const CAS_RIGHTS_COMB_COUNT: usize = 16;
+const KEY_SEED: u64 = 0x_F0C5_EE00_2020_0114;
+
+pub struct ZobKeys {
+    piece_keys: [[u64; def::BOARD_SIZE]; PIECE_TYPE_COUNT],
+    player_key: u64,
+    cas_rights_keys: [u64; CAS_RIGHTS_COMB_COUNT],
+    enp_sqr_keys: [u64; def::BOARD_SIZE],
+}
+
+pub static ZOB_KEYS: ZobKeys = gen_zob_keys();
+
+#[inline]
+pub fn get_piece_key(piece: u8, index: usize) -> u64 {
+    ZOB_KEYS.piece_keys[map_piece_to_key_index(piece)][index]
+}
+
+#[inline]
+pub fn get_player_key() -> u64 {
+    ZOB_KEYS.player_key
+}
+
+#[inline]
+pub fn get_cas_rights_key(cas_rights: u8) -> u64 {
+    ZOB_KEYS.cas_rights_keys[cas_rights as usize]
+}
+
+#[inline]
+pub fn get_enp_sqr_key(enp_sqr: usize) -> u64 {
+    ZOB_KEYS.enp_sqr_keys[enp_sqr]
+}
+
+#[inline]
+pub const fn map_piece_to_key_index(piece: u8) -> usize {
+    ((piece >> 2).trailing_zeros() * 2 + (piece & 1) as u32) as usize
+}
+
+pub const fn next_key(seed: &mut u64) -> u64 {
+    *seed = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+    let mut key = *seed;
+    key = (key ^ (key >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    key = (key ^ (key >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    key ^ (key >> 31)
+}
+
+const fn gen_zob_keys() -> ZobKeys {
+    let mut seed = KEY_SEED;
+
+    let mut piece_keys = [[0; def::BOARD_SIZE]; PIECE_TYPE_COUNT];
+    let mut piece_index = 0;
+    while piece_index < PIECE_TYPE_COUNT {
+        let mut sqr_index = 0;
+        while sqr_index < def::BOARD_SIZE {
+            if def::is_index_valid(sqr_index) {
+                piece_keys[piece_index][sqr_index] = next_key(&mut seed);
+            }
+
+            sqr_index += 1;
+        }
+
+        piece_index += 1;
+    }
+
+    let player_key = next_key(&mut seed);
+
+    let mut cas_rights_keys = [0; CAS_RIGHTS_COMB_COUNT];
+    let mut cas_rights = 1;
+    while cas_rights < CAS_RIGHTS_COMB_COUNT {
+        cas_rights_keys[cas_rights] = next_key(&mut seed);
+        cas_rights += 1;
+    }
+
+    let mut enp_sqr_keys = [0; def::BOARD_SIZE];
+    let mut sqr_index = 0;
+    while sqr_index < def::BOARD_SIZE {
+        if def::is_index_valid(sqr_index) && ((sqr_index >= 32 && sqr_index < 40) || (sqr_index >= 80 && sqr_index < 88)) {
+            enp_sqr_keys[sqr_index] = next_key(&mut seed);
+        }
+
+        sqr_index += 1;
+    }
+
+    ZobKeys {
+        piece_keys,
+        player_key,
+        cas_rights_keys,
+        enp_sqr_keys,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::def;
+
+    #[test]
+    fn test_map_piece_to_key_index() {
+        let piece_list = [def::WP, def::BP, def::WN, def::BN, def::WB, def::BB, def::WR, def::BR, def::WQ, def::BQ, def::WK, def::BK];
+
+        for (key_index, piece) in piece_list.iter().enumerate() {
+            assert_eq!(key_index, map_piece_to_key_index(*piece));
+        }
+    }
+
+    #[test]
+    fn test_keys_unique() {
+        let mut key_list = Vec::new();
+
+        for piece_keys in ZOB_KEYS.piece_keys.iter() {
+            for (index, key) in piece_keys.iter().enumerate() {
+                if def::is_index_valid(index) {
+                    key_list.push(*key);
+                }
+            }
+        }
+
+        key_list.push(ZOB_KEYS.player_key);
+        key_list.extend_from_slice(&ZOB_KEYS.cas_rights_keys[1..]);
+
+        for key in ZOB_KEYS.enp_sqr_keys.iter() {
+            if *key != 0 {
+                key_list.push(*key);
+            }
+        }
+
+        let key_count = key_list.len();
+        assert_eq!(12 * 64 + 1 + 15 + 16, key_count);
+        assert!(!key_list.contains(&0));
+
+        key_list.sort();
+        key_list.dedup();
+        assert_eq!(key_count, key_list.len());
+    }
+}